@@ -0,0 +1,312 @@
+use std::path::PathBuf;
+
+use lab4::headless::RenderOptions;
+use lab4::scene::MeshSource;
+use lab4::renderer::{Msaa, ColorFormat, ToneMap};
+use lab4::shaders::ShaderKind;
+
+pub const USAGE: &str = "\
+uso: lab4 [view|render|bench|export|help] [opciones]
+
+comandos:
+  view      abre la ventana interactiva (por defecto)
+  render    escribe frames PNG sin ventana
+  bench     mide el tiempo de render sin escribir nada
+  export    guarda la malla de un planeta (--planet) como .obj o .ply
+
+escena:
+  --width N, --height N   tamaño en píxeles (960x540)
+  --size ANCHOxALTO       ambos a la vez
+  --mesh [PLANETA=]MALLA  malla de todos los planetas, o solo de PLANETA (repetible):
+                          ico[:NIVEL] (ico:3, por defecto), uv[:SEGxANILLOS], cube[:DIV]
+                          o un archivo .obj, .gltf, .glb o .ply
+  --planet NOMBRE         un solo planeta: rocky, gas, scifi, rings, moon, flat, lava, ice
+  --all                   fila con todos los planetas (por defecto)
+  --no-rings, --no-moon   ocultan anillos / luna
+  --no-shadows            sin shadow map
+  --scene ARCHIVO         cámara, luz y planetas de un archivo JSON (en lugar de
+                          --planet, --all, --no-rings, --no-moon y --terrain)
+  --terrain ALTURA        relieve geométrico del planeta rocoso, fracción del radio
+                          (0.05; 0 = esfera lisa)
+  --time T                tiempo de simulación inicial, en segundos
+  --seed N                semilla del grano de película
+
+salida (render, bench; --out también en view para capturas):
+  --out DIR               carpeta de salida (renders; screenshots en view)
+  --frames N              cantidad de frames (1 en render, 60 en bench)
+  --duration S            alternativa a --frames: S segundos de animación a --fps
+  --fps F                 paso de simulación entre frames = 1/F (30)
+  --orbit GRADOS          gira la cámara alrededor de la escena, en grados por segundo
+  --gif                   (render) escribe también animation.gif con la secuencia
+
+exportar (export):
+  --out ARCHIVO           .obj (con su .mtl) o .ply (renders/PLANETA.obj)
+  --bake                  pasa la malla por el shader en --time: geometría desplazada
+                          y color por vértice
+
+imagen:
+  --msaa 1|2|4|8
+  --hdr                   target en punto flotante
+  --tonemap aces|reinhard|clamp
+  --exposure STOPS
+  --post LISTA            p. ej. bloom,fxaa,vignette,grain,lut=warm
+";
+
+/// Subcomando elegido
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command { View, Render, Bench, Export, Help }
+
+impl Command {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "view" => Some(Self::View),
+            "render" => Some(Self::Render),
+            "bench" => Some(Self::Bench),
+            "export" => Some(Self::Export),
+            "help" => Some(Self::Help),
+            _ => None,
+        }
+    }
+}
+
+/// Línea de comandos ya validada
+#[derive(Clone, Debug)]
+pub struct Cli {
+    pub command: Command,
+    pub opts: RenderOptions,
+    /// (export) archivo de salida
+    pub export_path: PathBuf,
+    /// (export) hornear el shader en la malla
+    pub bake: bool,
+}
+
+/// Interpreta los argumentos (sin el nombre del programa)
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, String> {
+    let mut args = args.into_iter().peekable();
+    let command = match args.peek() {
+        Some(a) if !a.starts_with('-') => {
+            let c = Command::parse(a).ok_or_else(|| format!("comando desconocido: '{a}' (usa view, render, bench, export o help)"))?;
+            args.next();
+            c
+        }
+        _ => Command::View,
+    };
+
+    let mut opts = RenderOptions::default();
+    let (mut out, mut frames, mut fps, mut duration) = (None, None, None, None);
+    let (mut all, mut bake) = (false, false);
+    // Primera opción de la escena de planetas, que no se combina con --scene
+    let mut showcase_flag: Option<String> = None;
+
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("falta valor para {flag}"));
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Cli { command: Command::Help, opts, export_path: PathBuf::new(), bake }),
+            "--width" => opts.width = positive(&flag, &value()?)?,
+            "--height" => opts.height = positive(&flag, &value()?)?,
+            "--size" => {
+                let v = value()?;
+                let (w, h) = v.split_once('x')
+                    .ok_or_else(|| format!("{flag} inválido: '{v}' (usa ANCHOxALTO, p. ej. 1280x720)"))?;
+                opts.width = positive(&flag, w)?;
+                opts.height = positive(&flag, h)?;
+            }
+            "--mesh" => {
+                let v = value()?;
+                match v.split_once('=').and_then(|(k, m)| Some((ShaderKind::parse(k)?, m))) {
+                    Some((kind, m)) => opts.meshes.set(kind, MeshSource::parse(m)?),
+                    None => opts.meshes.default = MeshSource::parse(&v)?,
+                }
+            }
+            "--planet" => {
+                let v = value()?;
+                if command != Command::Export { showcase_flag.get_or_insert(flag.clone()); }
+                if v == "all" {
+                    opts.scene.show_all = true;
+                    all = true;
+                } else {
+                    opts.scene.shader = ShaderKind::parse(&v).ok_or_else(|| format!(
+                        "{flag} inválido: '{v}' (usa rocky, gas, scifi, rings, moon, flat, lava, ice o all)"))?;
+                    opts.scene.show_all = false;
+                }
+            }
+            "--all" => { opts.scene.show_all = true; all = true; showcase_flag.get_or_insert(flag.clone()); }
+            "--no-rings" => { opts.scene.show_rings = false; showcase_flag.get_or_insert(flag.clone()); }
+            "--no-moon" => { opts.scene.show_moon = false; showcase_flag.get_or_insert(flag.clone()); }
+            "--no-shadows" => opts.scene.shadows = false,
+            "--scene" => opts.scene_file = Some(PathBuf::from(value()?)),
+            "--terrain" => {
+                showcase_flag.get_or_insert(flag.clone());
+                let h = finite(&flag, &value()?)?;
+                if h < 0.0 { return Err(format!("{flag} no puede ser negativo")); }
+                opts.scene.terrain = h;
+            }
+            "--time" => opts.start_time = finite(&flag, &value()?)?,
+            "--seed" => {
+                let v = value()?;
+                opts.seed = v.parse().map_err(|_| format!("{flag} inválido: '{v}' (entero no negativo)"))?;
+            }
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--frames" => frames = Some(positive(&flag, &value()?)?),
+            "--duration" => {
+                let d = finite(&flag, &value()?)?;
+                if d <= 0.0 { return Err(format!("{flag} debe ser mayor que 0")); }
+                duration = Some(d);
+            }
+            "--orbit" => opts.orbit_speed = Some(finite(&flag, &value()?)?.to_radians()),
+            "--gif" => opts.gif = true,
+            "--bake" => bake = true,
+            "--fps" => {
+                let f = finite(&flag, &value()?)?;
+                if f <= 0.0 { return Err(format!("{flag} debe ser mayor que 0")); }
+                fps = Some(f);
+            }
+            "--msaa" => {
+                let v = value()?;
+                opts.framebuffer.msaa = v.parse().ok().and_then(Msaa::from_samples)
+                    .ok_or_else(|| format!("{flag} inválido: '{v}' (usa 1, 2, 4 u 8)"))?;
+            }
+            "--hdr" => opts.framebuffer.format = ColorFormat::HdrF32,
+            "--tonemap" => {
+                let v = value()?;
+                opts.framebuffer.tone.op = ToneMap::parse(&v)
+                    .ok_or_else(|| format!("{flag} inválido: '{v}' (usa aces, reinhard o clamp)"))?;
+            }
+            "--exposure" => opts.framebuffer.tone.exposure = finite(&flag, &value()?)?,
+            "--post" => opts.framebuffer.post = value()?,
+            other if other.starts_with('-') => return Err(format!("opción desconocida: {other}")),
+            other => return Err(format!("argumento inesperado: '{other}'")),
+        }
+    }
+
+    let animated = matches!(command, Command::Render | Command::Bench);
+    if !animated && (frames.is_some() || fps.is_some() || duration.is_some() || opts.orbit_speed.is_some()) {
+        return Err("--frames, --duration, --fps y --orbit solo aplican a render y bench".into());
+    }
+    if let (Some(_), Some(flag)) = (&opts.scene_file, &showcase_flag) {
+        return Err(format!("{flag} no se combina con --scene: el archivo describe los planetas"));
+    }
+    if bake && command != Command::Export {
+        return Err("--bake solo aplica a export".into());
+    }
+    if all && command == Command::Export {
+        return Err("export guarda un solo planeta: elígelo con --planet".into());
+    }
+    if opts.gif && command != Command::Render {
+        return Err("--gif solo aplica a render".into());
+    }
+    if let Some(f) = fps { opts.fps = f; }
+    opts.frames = match (frames, duration) {
+        (Some(_), Some(_)) => return Err("usa --frames o --duration, no ambos".into()),
+        (Some(n), None) => n,
+        (None, Some(d)) => ((d * opts.fps).round() as u32).max(1),
+        (None, None) => if command == Command::Bench { 60 } else { 1 },
+    };
+    let export_path = match (command, &out) {
+        (Command::Export, Some(file)) => file.clone(),
+        (Command::Export, None) => opts.out_dir.join(format!("{}.obj", opts.scene.shader.name())),
+        _ => PathBuf::new(),
+    };
+    if command == Command::Export {
+        let ext = export_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+        if ext != "obj" && ext != "ply" {
+            return Err(format!("--out '{}': export escribe .obj o .ply", export_path.display()));
+        }
+    } else {
+        opts.out_dir = out.unwrap_or_else(|| if command == Command::View { "screenshots".into() } else { opts.out_dir.clone() });
+    }
+
+    // El post-proceso se valida acá para que el error salga antes de abrir la ventana
+    opts.framebuffer.build(1, 1)?;
+    Ok(Cli { command, opts, export_path, bake })
+}
+
+fn positive<T: std::str::FromStr + Default + PartialOrd>(flag: &str, v: &str) -> Result<T, String> {
+    v.parse().ok().filter(|n| *n > T::default())
+        .ok_or_else(|| format!("{flag} inválido: '{v}' (entero mayor que 0)"))
+}
+
+fn finite(flag: &str, v: &str) -> Result<f32, String> {
+    v.parse().ok().filter(|f: &f32| f.is_finite())
+        .ok_or_else(|| format!("{flag} inválido: '{v}' (número)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> { s.split_whitespace().map(String::from).collect() }
+
+    #[test]
+    fn render_parses_size_planet_and_output() {
+        let cli = parse(args("render --size 320x200 --planet gas --no-rings --time 2.5 --seed 7 --frames 3 --out tmp")).unwrap();
+        assert_eq!(cli.command, Command::Render);
+        assert_eq!((cli.opts.width, cli.opts.height, cli.opts.frames, cli.opts.seed), (320, 200, 3, 7));
+        assert_eq!(cli.opts.scene.shader, ShaderKind::Gas);
+        assert!(!cli.opts.scene.show_all && !cli.opts.scene.show_rings && cli.opts.scene.show_moon);
+        assert_eq!(cli.opts.start_time, 2.5);
+        assert_eq!(cli.opts.out_dir, PathBuf::from("tmp"));
+    }
+
+    #[test]
+    fn reports_bad_values() {
+        assert_eq!(parse(args("render --width")).unwrap_err(), "falta valor para --width");
+        assert!(parse(args("render --width 0")).unwrap_err().contains("--width inválido"));
+        assert!(parse(args("render --size 640")).unwrap_err().contains("ANCHOxALTO"));
+        assert!(parse(args("--planet pluto")).unwrap_err().contains("'pluto'"));
+        assert!(parse(args("--frobnicate")).unwrap_err().contains("desconocida"));
+        assert!(parse(args("draw")).unwrap_err().contains("comando desconocido"));
+        assert!(parse(args("render --post sparkle")).is_err());
+    }
+
+    #[test]
+    fn mesh_flags_pick_a_source_per_planet() {
+        let meshes = parse(args("--mesh uv:32x16 --mesh rings=ico:2 --mesh moon=assets/moon.obj")).unwrap().opts.meshes;
+        assert_eq!(meshes.default, MeshSource::UvSphere { segments: 32, rings: 16 });
+        assert_eq!(*meshes.get(ShaderKind::Rings), MeshSource::Icosphere(2));
+        assert_eq!(*meshes.get(ShaderKind::Moon), MeshSource::File("assets/moon.obj".into()));
+        assert_eq!(*meshes.get(ShaderKind::Gas), meshes.default);
+        assert!(parse(args("--mesh ico:9")).unwrap_err().contains("entre 0 y 7"));
+    }
+
+    #[test]
+    fn view_bench_and_turntable_frames() {
+        // Sin subcomando abre la ventana; bench mide 60 frames por defecto
+        assert_eq!(parse(args("--hdr")).unwrap().command, Command::View);
+        assert_eq!(parse(args("bench")).unwrap().opts.frames, 60);
+        let turntable = parse(args("render --duration 2 --fps 12 --orbit 90 --gif")).unwrap().opts;
+        assert_eq!(turntable.frames, 24);
+        assert!(turntable.gif && (turntable.orbit_speed.unwrap() - std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+
+        assert!(parse(args("view --frames 2")).is_err());
+        assert!(parse(args("render --frames 2 --duration 1")).is_err());
+        assert!(parse(args("bench --gif")).is_err());
+    }
+
+    #[test]
+    fn export_picks_path_format_and_bake() {
+        let export = parse(args("export --planet rings --mesh uv --bake")).unwrap();
+        assert_eq!((export.command, export.bake), (Command::Export, true));
+        assert_eq!(export.export_path, PathBuf::from("renders/rings.obj"));
+        assert_eq!(parse(args("export --planet ice --out a/b.ply")).unwrap().export_path, PathBuf::from("a/b.ply"));
+        assert!(parse(args("export --planet ice --out hielo.stl")).unwrap_err().contains(".obj o .ply"));
+        assert!(parse(args("export --all")).is_err());
+        assert!(parse(args("export --frames 3")).is_err());
+        assert!(parse(args("render --bake")).is_err());
+    }
+
+    #[test]
+    fn terrain_amount() {
+        assert_eq!(parse(args("--terrain 0.1")).unwrap().opts.scene.terrain, 0.1);
+        assert_eq!(parse(args("render --terrain 0")).unwrap().opts.scene.terrain, 0.0);
+        assert!(parse(args("--terrain -0.1")).unwrap_err().contains("negativo"));
+    }
+
+    #[test]
+    fn scene_file_replaces_the_planet_flags() {
+        assert_eq!(parse(args("render --scene a.json")).unwrap().opts.scene_file, Some(PathBuf::from("a.json")));
+        assert!(parse(args("export --scene a.json --planet gas")).is_ok());
+        assert_eq!(parse(args("--scene a.json --no-moon")).unwrap_err(), "--no-moon no se combina con --scene: el archivo describe los planetas");
+        assert!(parse(args("render --planet gas --scene a.json")).is_err());
+    }
+}
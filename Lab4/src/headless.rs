@@ -0,0 +1,344 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+
+use crate::renderer::bake_mesh;
+use crate::renderer::buffers::{Color, FramebufferSettings, to_image};
+use crate::renderer::raster::DrawStats;
+use crate::scene::{Camera, Mesh, MeshSource, NBody, PlanetMeshes, Scene, Showcase, load_scene, save_mesh};
+use crate::scene::showcase::load_meshes;
+
+/// Qué y cómo renderizar. Lo usan la ventana, el render a PNG y el benchmark.
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    pub width: usize,
+    pub height: usize,
+    /// Cantidad de frames a escribir
+    pub frames: u32,
+    /// Tiempo de simulación del primer frame, en segundos
+    pub start_time: f32,
+    /// Frames por segundo de simulación (paso entre frames = `1/fps`)
+    pub fps: f32,
+    /// Semilla de los efectos aleatorios (grano de película)
+    pub seed: u64,
+    /// Gira la cámara alrededor de la escena a esta velocidad, en rad/s
+    pub orbit_speed: Option<f32>,
+    /// Además de los PNG, escribe la secuencia como `animation.gif`
+    pub gif: bool,
+    pub out_dir: PathBuf,
+    /// Malla de cada planeta
+    pub meshes: PlanetMeshes<MeshSource>,
+    pub scene: Showcase,
+    /// Escena de un archivo JSON (`scene::file`) en lugar de `scene`
+    pub scene_file: Option<PathBuf>,
+    pub framebuffer: FramebufferSettings,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 960,
+            height: 540,
+            frames: 1,
+            start_time: 0.0,
+            fps: 30.0,
+            seed: 0,
+            orbit_speed: None,
+            gif: false,
+            out_dir: PathBuf::from("renders"),
+            meshes: PlanetMeshes::default(),
+            scene: Showcase::default(),
+            scene_file: None,
+            framebuffer: FramebufferSettings::default(),
+        }
+    }
+}
+
+/// Nombre del GIF animado dentro de `out_dir`
+pub const GIF_NAME: &str = "animation.gif";
+
+/// Ruta del frame `i`: `frame_0000.png`, `frame_0001.png`, ...
+pub fn frame_path(dir: &Path, i: u32) -> PathBuf {
+    dir.join(format!("frame_{i:04}.png"))
+}
+
+impl RenderOptions {
+    /// Tiempo de simulación del frame `i`
+    pub fn frame_time(&self, i: u32) -> f32 {
+        let dt = if self.fps > 0.0 { 1.0 / self.fps } else { 0.0 };
+        self.start_time + i as f32 * dt
+    }
+
+    /// Escena a dibujar: la de `scene_file` o la de `scene`, con la cámara ajustada al tamaño.
+    /// `scene.shadows` en falso también apaga las sombras del archivo.
+    pub fn load_scene(&self) -> Result<Scene, String> {
+        let scene = match &self.scene_file {
+            Some(path) => load_scene(path)?,
+            None => self.scene.scene(),
+        };
+        Ok(self.fit_scene(scene))
+    }
+
+    /// Aplica a una escena ya leída (p. ej. recargada de disco) lo mismo que `load_scene`
+    pub fn fit_scene(&self, mut scene: Scene) -> Scene {
+        scene.shadows &= self.scene.shadows;
+        scene.camera.aspect = self.width as f32 / self.height.max(1) as f32;
+        scene
+    }
+
+    /// Mallas de cada planeta para `scene` (de `load_scene`)
+    pub fn load_meshes(&self, scene: &Scene) -> Result<PlanetMeshes<Mesh>, String> {
+        match self.scene_file {
+            Some(_) => load_meshes(&self.meshes, &scene.root),
+            None => self.scene.load_meshes(&self.meshes),
+        }
+    }
+
+    /// Cámara del frame `i`: `base`, o con `orbit_speed` girada alrededor de su centro.
+    /// El radio crece si hace falta para no atravesar una escena de radio `radius`.
+    pub fn frame_camera(&self, base: &Camera, radius: f32, i: u32) -> Camera {
+        let Some(speed) = self.orbit_speed else { return *base };
+        let offset = base.eye - base.center;
+        let dist = offset.length();
+        let mut cam = *base;
+        // Ángulos de `auto_orbit` que reproducen la posición inicial
+        cam.yaw = offset.z.atan2(offset.x);
+        cam.pitch = if dist > 0.0 { (offset.y / dist).asin() } else { 0.0 };
+        cam.auto_orbit(dist.max(radius * 1.2), speed, self.frame_time(i) - self.start_time);
+        cam
+    }
+}
+
+/// Renderiza `frames` frames sin ventana y devuelve las rutas escritas:
+/// los PNG en orden y, con `gif`, el GIF al final
+pub fn render_headless(opts: &RenderOptions) -> Result<Vec<PathBuf>, String> {
+    let mut written = Vec::with_capacity(opts.frames as usize + 1);
+    std::fs::create_dir_all(&opts.out_dir).map_err(|e| format!("{}: {e}", opts.out_dir.display()))?;
+
+    let gif_path = opts.out_dir.join(GIF_NAME);
+    let mut gif = if opts.gif {
+        let file = File::create(&gif_path).map_err(|e| format!("{}: {e}", gif_path.display()))?;
+        let mut enc = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+        enc.set_repeat(Repeat::Infinite).map_err(|e| format!("{}: {e}", gif_path.display()))?;
+        Some(enc)
+    } else {
+        None
+    };
+    let delay = Delay::from_saturating_duration(Duration::from_secs_f32(if opts.fps > 0.0 { 1.0 / opts.fps } else { 0.1 }));
+
+    render_frames(opts, |i, pixels| {
+        let img = to_image(opts.width, opts.height, pixels);
+        let path = frame_path(&opts.out_dir, i);
+        img.save(&path).map_err(|e| format!("No pude guardar '{}': {e}", path.display()))?;
+        written.push(path);
+        if let Some(enc) = &mut gif {
+            enc.encode_frame(Frame::from_parts(img, 0, 0, delay)).map_err(|e| format!("{}: {e}", gif_path.display()))?;
+        }
+        Ok(())
+    })?;
+
+    if gif.take().is_some() { written.push(gif_path); }
+    Ok(written)
+}
+
+/// Tiempos de un benchmark
+#[derive(Clone, Debug, Default)]
+pub struct BenchReport {
+    pub frames: u32,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    /// Estadísticas acumuladas de todos los frames
+    pub stats: DrawStats,
+    /// Con gravedad, la mayor deriva relativa de la energía de la simulación
+    pub energy_drift: Option<f64>,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let avg = if self.frames > 0 { ms(self.total) / self.frames as f64 } else { 0.0 };
+        writeln!(f, "{} frames en {:.1} ms", self.frames, ms(self.total))?;
+        writeln!(f, "  por frame: media {avg:.2} ms ({:.1} fps), mín {:.2} ms, máx {:.2} ms",
+                 if avg > 0.0 { 1000.0 / avg } else { 0.0 }, ms(self.min), ms(self.max))?;
+        write!(f, "  triángulos {}, fragmentos {} (early-z evitó {})",
+               self.stats.triangles, self.stats.fragments_shaded, self.stats.fragments_early_rejected)?;
+        if let Some(drift) = self.energy_drift {
+            write!(f, "\n  gravedad: deriva máxima de energía {:.2e}", drift)?;
+        }
+        Ok(())
+    }
+}
+
+/// Renderiza y resuelve `frames` frames sin escribir nada, midiendo cada uno
+pub fn bench(opts: &RenderOptions) -> Result<BenchReport, String> {
+    let mut report = BenchReport { min: Duration::MAX, ..Default::default() };
+    render_frames(opts, |_, _| Ok(()))
+        .map(|(times, energy_drift)| {
+            report.energy_drift = energy_drift;
+            for (dt, stats) in times {
+                report.frames += 1;
+                report.total += dt;
+                report.min = report.min.min(dt);
+                report.max = report.max.max(dt);
+                report.stats += stats;
+            }
+        })?;
+    if report.frames == 0 { report.min = Duration::ZERO; }
+    Ok(report)
+}
+
+/// Guarda la malla del planeta `scene.shader` en `path` (`.obj` o `.ply`) y la devuelve.
+/// Con `bake` la malla pasa antes por el shader del primer nodo de la escena que dibuja
+/// ese planeta, en `start_time` con la cámara de la escena y sin sombras: se exportan la
+/// geometría desplazada y el color por vértice.
+pub fn export_mesh(opts: &RenderOptions, path: &Path, bake: bool) -> Result<Mesh, String> {
+    let kind = opts.scene.shader;
+    let mut scene = opts.load_scene()?;
+    scene.advance(opts.start_time);
+    let mut mesh = opts.load_meshes(&scene)?.get(kind).clone();
+    if bake {
+        let (shader, world) = scene.root.find_shader(kind, opts.start_time)
+            .ok_or_else(|| format!("la escena no dibuja el planeta {}", kind.name()))?;
+        let mut uniforms = scene.uniforms(&scene.camera, opts.start_time);
+        uniforms.model = world;
+        mesh = bake_mesh(&mesh, &mut *shader.build(), &uniforms);
+    }
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    save_mesh(&mesh, &path.to_string_lossy())?;
+    Ok(mesh)
+}
+
+/// Tiempo de render+resolve y estadísticas de cada frame
+type FrameTimes = Vec<(Duration, DrawStats)>;
+
+/// Bucle común: dibuja y resuelve cada frame, luego llama a `each(i, píxeles)`.
+/// Devuelve los `FrameTimes` y, con gravedad, la mayor deriva de energía de la simulación.
+fn render_frames<F>(opts: &RenderOptions, mut each: F) -> Result<(FrameTimes, Option<f64>), String>
+where
+    F: FnMut(u32, &[Color]) -> Result<(), String>,
+{
+    if opts.width == 0 || opts.height == 0 {
+        return Err(format!("tamaño inválido: {}x{}", opts.width, opts.height));
+    }
+    let mut scene = opts.load_scene()?;
+    let meshes = opts.load_meshes(&scene)?;
+    let mut fb = opts.framebuffer.build(opts.width, opts.height)?;
+    let mut shadow_map = None;
+
+    let mut times = Vec::with_capacity(opts.frames as usize);
+    for i in 0..opts.frames {
+        let t0 = Instant::now();
+        scene.advance(opts.frame_time(i));
+        let uniforms = scene.uniforms(&opts.frame_camera(&scene.camera, scene.radius, i), opts.frame_time(i));
+        let stats = scene.render(&mut fb, &meshes, &uniforms, &mut shadow_map);
+        fb.post.frame = opts.seed.wrapping_add(i as u64);
+        let pixels = fb.resolve();
+        times.push((t0.elapsed(), stats));
+        each(i, pixels)?;
+    }
+    Ok((times, scene.gravity.as_ref().map(NBody::max_energy_drift)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shaders::ShaderKind;
+    use crate::utils::testing::temp_dir;
+
+    /// Un solo planeta `kind` con el resto de las opciones por defecto
+    fn single(kind: ShaderKind) -> RenderOptions {
+        RenderOptions { scene: Showcase { shader: kind, show_all: false, ..Default::default() }, ..Default::default() }
+    }
+
+    #[test]
+    fn writes_numbered_pngs() {
+        let out_dir = temp_dir("headless");
+        let opts = RenderOptions { width: 64, height: 36, frames: 2, out_dir: out_dir.clone(), ..single(ShaderKind::Gas) };
+        let paths = render_headless(&opts).unwrap();
+        assert_eq!(paths, [frame_path(&out_dir, 0), frame_path(&out_dir, 1)]);
+
+        let img = image::open(&paths[1]).unwrap().to_rgba8();
+        assert_eq!(img.dimensions(), (64, 36));
+        // Hay planeta en el centro (no es solo fondo)
+        assert_ne!(img.get_pixel(32, 18).0, [5, 8, 12, 255]);
+        std::fs::remove_dir_all(&out_dir).ok();
+    }
+
+    #[test]
+    fn turntable_orbits_camera_and_writes_gif() {
+        use image::AnimationDecoder;
+        use image::codecs::gif::GifDecoder;
+
+        let out_dir = temp_dir("turntable");
+        let opts = RenderOptions {
+            width: 48, height: 32, frames: 3, fps: 2.0, out_dir: out_dir.clone(),
+            orbit_speed: Some(std::f32::consts::FRAC_PI_2), gif: true,
+            ..single(ShaderKind::Flat)
+        };
+
+        // Cuarto de vuelta por segundo: en el frame 2 (t = 1 s) la cámara pasó de +z a -x
+        let base = crate::scene::showcase::default_camera(1.5);
+        assert!((opts.frame_camera(&base, 3.0, 0).eye - base.eye).length() < 1e-5);
+        let eye = opts.frame_camera(&base, 3.0, 2).eye;
+        assert!((eye.x + 4.0).abs() < 1e-4 && eye.z.abs() < 1e-4);
+
+        let paths = render_headless(&opts).unwrap();
+        assert_eq!(paths.len(), 4);
+        assert_eq!(paths[3], out_dir.join(GIF_NAME));
+        let gif = GifDecoder::new(std::io::BufReader::new(File::open(&paths[3]).unwrap())).unwrap();
+        assert_eq!(gif.into_frames().count(), 3);
+        std::fs::remove_dir_all(&out_dir).ok();
+    }
+
+    #[test]
+    fn export_bakes_the_planet_shader() {
+        let dir = temp_dir("export");
+        let path = dir.join("anillos.ply");
+        let opts = RenderOptions { meshes: PlanetMeshes::new(MeshSource::Icosphere(2)), ..single(ShaderKind::Rings) };
+        let plain = export_mesh(&opts, &path, false).unwrap();
+        let baked = export_mesh(&opts, &path, true).unwrap();
+        let back = crate::scene::load_mesh(path.to_str().unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+        let back = back.unwrap();
+
+        assert!(plain.colors.is_empty());
+        // El vertex shader de los anillos estira la esfera en un disco y el fragment le da color
+        let extent = |m: &Mesh| m.vertices.iter().map(|v| v.pos.length()).fold(0.0, f32::max);
+        assert!(extent(&baked) > 1.5 * extent(&plain));
+        assert_eq!(baked.colors.len(), baked.vertices.len());
+        assert!(baked.colors.iter().any(|c| c.length() > 0.1));
+        assert_eq!((back.vertices.len(), back.colors.len()), (baked.vertices.len(), baked.vertices.len()));
+    }
+
+    #[test]
+    fn terrain_refines_and_displaces_the_rocky_planet() {
+        let dir = temp_dir("terrain");
+        let path = dir.join("rocky.obj");
+        let mut opts = RenderOptions { meshes: PlanetMeshes::new(MeshSource::Icosphere(2)), ..single(ShaderKind::Rocky) };
+        opts.scene.terrain = 0.0;
+        let smooth = export_mesh(&opts, &path, true).unwrap();
+        opts.scene.terrain = 0.08;
+        let plain = export_mesh(&opts, &path, false).unwrap();
+        let relief = export_mesh(&opts, &path, true).unwrap();
+        opts.start_time = 40.0;
+        let later = export_mesh(&opts, &path, true).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        // Sin relieve la esfera queda igual; con relieve se subdivide y el radio varía
+        let radii = |m: &Mesh| m.vertices.iter().map(|v| v.pos.length()).fold((f32::MAX, 0.0f32), |(lo, hi), r| (lo.min(r), hi.max(r)));
+        let (lo, hi) = radii(&smooth);
+        assert!(hi - lo < 1e-4);
+        assert!(plain.indices.len() > smooth.indices.len());
+        let (lo, hi) = radii(&relief);
+        assert!(hi - lo > 0.02 && hi - lo < 0.1, "radio entre {lo} y {hi}");
+        // El relieve no se mueve con el tiempo: el refinado de la malla sigue valiendo
+        assert!(relief.vertices.iter().zip(&later.vertices).all(|(a, b)| (a.pos.length() - b.pos.length()).abs() < 1e-5));
+    }
+}
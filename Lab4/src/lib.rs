@@ -0,0 +1,35 @@
+//! Rasterizador por software de planetas procedurales.
+//!
+//! - [`math`]: vectores, matrices y ruido.
+//! - [`renderer`]: framebuffer, pipeline (`Shader`, `PipelineState`, `draw_mesh`), cola de
+//!   render con transparencias y sombras, tone mapping y post-proceso.
+//! - [`shaders`]: los planetas (`ShaderKind`, `ShaderParams`, `make_shader`) y utilidades para
+//!   escribir shaders.
+//! - [`scene`]: mallas (OBJ, glTF, PLY o esferas generadas), cámara, grafo de escena (`Node`),
+//!   órbitas keplerianas (`Orbit`) o simuladas con gravedad (`NBody`) y la escena de planetas
+//!   (`Showcase`).
+//! - [`headless`]: render a PNG y benchmark sin ventana.
+//!
+//! La ventana (winit + pixels) vive solo en el binario, detrás de la feature `window`.
+//!
+//! ```
+//! use lab4::headless::{RenderOptions, render_headless};
+//! use lab4::scene::Showcase;
+//! use lab4::shaders::ShaderKind;
+//!
+//! let opts = RenderOptions {
+//!     width: 32, height: 32,
+//!     out_dir: std::env::temp_dir().join("lab4_doc"),
+//!     scene: Showcase { shader: ShaderKind::Ice, show_all: false, ..Default::default() },
+//!     ..Default::default()
+//! };
+//! let frames = render_headless(&opts).unwrap();
+//! assert_eq!(frames.len(), 1);
+//! ```
+
+pub mod math;
+pub mod renderer;
+pub mod shaders;
+pub mod scene;
+pub mod headless;
+pub mod utils;
@@ -36,12 +36,12 @@ impl Mul for Mat4 {
     type Output = Mat4;
     fn mul(self, o: Mat4) -> Mat4 {
         let mut r = [[0.0;4];4];
-        for i in 0..4 {
-            for j in 0..4 {
-                r[i][j] = self.m[i][0]*o.m[0][j]
-                        + self.m[i][1]*o.m[1][j]
-                        + self.m[i][2]*o.m[2][j]
-                        + self.m[i][3]*o.m[3][j];
+        for (ri, a) in r.iter_mut().zip(&self.m) {
+            for (j, rij) in ri.iter_mut().enumerate() {
+                *rij = a[0]*o.m[0][j]
+                     + a[1]*o.m[1][j]
+                     + a[2]*o.m[2][j]
+                     + a[3]*o.m[3][j];
             }
         }
         Mat4 { m: r }
@@ -2,7 +2,7 @@ use super::vec::Vec3;
 
 /// Hash continuo
 #[inline] fn hash1(n: f32) -> f32 {
-    (n.sin()*43758.5453).fract()
+    (n.sin()*43_758.545).fract()
 }
 
 /// Hash 3D / escalar
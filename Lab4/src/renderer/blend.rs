@@ -0,0 +1,117 @@
+use super::buffers::{Color, ColorF};
+
+/// Factor de mezcla (estilo OpenGL)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+}
+
+impl BlendFactor {
+    #[inline]
+    fn value(self, src_a: f32, dst_a: f32) -> f32 {
+        match self {
+            BlendFactor::Zero             => 0.0,
+            BlendFactor::One              => 1.0,
+            BlendFactor::SrcAlpha         => src_a,
+            BlendFactor::OneMinusSrcAlpha => 1.0 - src_a,
+            BlendFactor::DstAlpha         => dst_a,
+            BlendFactor::OneMinusDstAlpha => 1.0 - dst_a,
+        }
+    }
+}
+
+/// `out = src*src_factor + dst*dst_factor`, con factores separados para alpha
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BlendState {
+    pub src: BlendFactor,
+    pub dst: BlendFactor,
+    pub src_alpha: BlendFactor,
+    pub dst_alpha: BlendFactor,
+}
+
+impl Default for BlendState {
+    fn default() -> Self { Self::OPAQUE }
+}
+
+impl BlendState {
+    /// Sobrescribe (sin mezcla)
+    pub const OPAQUE: Self = Self {
+        src: BlendFactor::One, dst: BlendFactor::Zero,
+        src_alpha: BlendFactor::One, dst_alpha: BlendFactor::Zero,
+    };
+    /// "Over": alpha no premultiplicado
+    pub const ALPHA: Self = Self {
+        src: BlendFactor::SrcAlpha, dst: BlendFactor::OneMinusSrcAlpha,
+        src_alpha: BlendFactor::One, dst_alpha: BlendFactor::OneMinusSrcAlpha,
+    };
+    /// Suma ponderada por alpha; conserva el alpha del destino
+    pub const ADDITIVE: Self = Self {
+        src: BlendFactor::SrcAlpha, dst: BlendFactor::One,
+        src_alpha: BlendFactor::Zero, dst_alpha: BlendFactor::One,
+    };
+
+    pub fn is_opaque(&self) -> bool { *self == Self::OPAQUE }
+
+    #[inline]
+    pub fn apply(&self, src: Color, dst: Color) -> Color {
+        if self.is_opaque() { return src; }
+
+        let sa = src.a as f32 / 255.0;
+        let da = dst.a as f32 / 255.0;
+        let (fs, fd) = (self.src.value(sa, da), self.dst.value(sa, da));
+        let (fsa, fda) = (self.src_alpha.value(sa, da), self.dst_alpha.value(sa, da));
+
+        #[inline(always)]
+        fn mix(s: u8, d: u8, fs: f32, fd: f32) -> u8 {
+            (s as f32 * fs + d as f32 * fd + 0.5).clamp(0.0, 255.0) as u8
+        }
+        Color::rgba(
+            mix(src.r, dst.r, fs, fd),
+            mix(src.g, dst.g, fs, fd),
+            mix(src.b, dst.b, fs, fd),
+            mix(src.a, dst.a, fsa, fda),
+        )
+    }
+
+    /// Igual que `apply` pero en flotante y sin recortar el color (destino HDR)
+    #[inline]
+    pub fn apply_f(&self, src: ColorF, dst: ColorF) -> ColorF {
+        if self.is_opaque() { return src; }
+
+        let sa = src.a.clamp(0.0, 1.0);
+        let da = dst.a.clamp(0.0, 1.0);
+        let (fs, fd) = (self.src.value(sa, da), self.dst.value(sa, da));
+        let (fsa, fda) = (self.src_alpha.value(sa, da), self.dst_alpha.value(sa, da));
+        ColorF::rgba(
+            src.r*fs + dst.r*fd,
+            src.g*fs + dst.g*fd,
+            src.b*fs + dst.b*fd,
+            (sa*fsa + da*fda).clamp(0.0, 1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_over() {
+        let dst = Color::rgb(0, 0, 200);
+        let out = BlendState::ALPHA.apply(Color::rgba(200, 0, 0, 128), dst);
+        assert!((out.r as i32 - 100).abs() <= 1);
+        assert!((out.b as i32 - 100).abs() <= 1);
+        assert_eq!(out.a, 255);
+    }
+
+    #[test]
+    fn additive_saturates() {
+        let out = BlendState::ADDITIVE.apply(Color::rgba(200, 10, 0, 255), Color::rgb(100, 10, 0));
+        assert_eq!((out.r, out.g, out.a), (255, 20, 255));
+    }
+}
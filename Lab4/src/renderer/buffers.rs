@@ -1,5 +1,8 @@
 use image::{RgbaImage, Rgba};
 
+use super::post::{PostChain, PostImage, PostStage};
+use super::tonemap::{ToneMap, ToneMapping};
+
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Color { pub r: u8, pub g: u8, pub b: u8, pub a: u8 }
 
@@ -12,25 +15,150 @@ impl Color {
         Self::rgb(to8(r), to8(g), to8(b))
     }
 
+    pub fn from_f32_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        fn to8(x: f32) -> u8 { (x.clamp(0.0, 1.0) * 255.0 + 0.5) as u8 }
+        Self::rgba(to8(r), to8(g), to8(b), to8(a))
+    }
+
     pub fn to_rgba(self) -> [u8;4] { [self.r, self.g, self.b, self.a] }
 }
 
+/// Color lineal en punto flotante, sin límite superior (radiancia HDR).
+/// Es lo que devuelve el fragment shader; el destino decide si lo recorta a 8 bits.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ColorF { pub r: f32, pub g: f32, pub b: f32, pub a: f32 }
+
+impl ColorF {
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self { Self { r, g, b, a } }
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self { Self { r, g, b, a: 1.0 } }
+
+    /// Recorta a [0,1] y cuantiza a 8 bits
+    #[inline]
+    pub fn to_color(self) -> Color { Color::from_f32_rgba(self.r, self.g, self.b, self.a) }
+}
+
+impl From<Color> for ColorF {
+    fn from(c: Color) -> Self {
+        Self::rgba(c.r as f32 / 255.0, c.g as f32 / 255.0, c.b as f32 / 255.0, c.a as f32 / 255.0)
+    }
+}
+
+/// Formato del color del framebuffer
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// RGBA8: cada fragmento se recorta a [0,1]
+    #[default]
+    Ldr8,
+    /// RGBA f32 lineal; `resolve()` aplica tone mapping
+    HdrF32,
+    /// Sin color, solo profundidad (pase de sombras); `resolve()` no da píxeles
+    Depth,
+}
+
+/// Modo de antialiasing multisample
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Msaa {
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    pub fn samples(self) -> usize {
+        match self { Msaa::Off => 1, Msaa::X2 => 2, Msaa::X4 => 4, Msaa::X8 => 8 }
+    }
+
+    pub fn from_samples(n: usize) -> Option<Self> {
+        match n { 0 | 1 => Some(Msaa::Off), 2 => Some(Msaa::X2), 4 => Some(Msaa::X4), 8 => Some(Msaa::X8), _ => None }
+    }
+
+    /// Posiciones de muestra relativas al centro del píxel, en 1/16 px (patrones estándar de D3D)
+    pub fn sample_offsets(self) -> &'static [(i32, i32)] {
+        match self {
+            Msaa::Off => &[(0, 0)],
+            Msaa::X2  => &[(4, 4), (-4, -4)],
+            Msaa::X4  => &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+            Msaa::X8  => &[(1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7)],
+        }
+    }
+}
+
+/// Cómo crear el framebuffer de la app; se guarda para recrearlo al cambiar de tamaño
+#[derive(Clone, Debug, Default)]
+pub struct FramebufferSettings {
+    pub msaa: Msaa,
+    pub format: ColorFormat,
+    pub tone: ToneMapping,
+    /// Cadena de post-proceso en el formato de `PostChain::parse`
+    pub post: String,
+}
+
+impl FramebufferSettings {
+    pub fn build(&self, width: usize, height: usize) -> Result<Framebuffer, String> {
+        let mut fb = Framebuffer::with_format(width, height, self.msaa, self.format);
+        fb.tone = self.tone;
+        fb.post = PostChain::parse(&self.post)?;
+        Ok(fb)
+    }
+}
+
+/// Color y profundidad por muestra: el píxel `i` ocupa `[i*samples, (i+1)*samples)`.
+/// Sin MSAA hay una muestra por píxel. `resolve()` promedia a un color por píxel.
+/// El color vive en `color` (LDR) o en `color_hdr` (HDR) según `format`; el otro queda vacío.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
+    pub msaa: Msaa,
+    pub format: ColorFormat,
     pub color: Vec<Color>,
+    pub color_hdr: Vec<ColorF>,
     pub depth: Vec<f32>, 
+    /// Tone mapping usado al resolver un destino HDR
+    pub tone: ToneMapping,
+    /// Post-proceso aplicado en `resolve()` (pantalla y capturas)
+    pub post: PostChain,
+    resolved: Vec<Color>,
 }
 
 impl Framebuffer {
     pub fn new(width: usize, height: usize) -> Self {
+        Self::with_msaa(width, height, Msaa::Off)
+    }
+
+    pub fn with_msaa(width: usize, height: usize, msaa: Msaa) -> Self {
+        Self::with_format(width, height, msaa, ColorFormat::Ldr8)
+    }
+
+    pub fn with_format(width: usize, height: usize, msaa: Msaa, format: ColorFormat) -> Self {
+        let n = width*height*msaa.samples();
+        let (color, color_hdr) = match format {
+            ColorFormat::Ldr8   => (vec![Color::rgb(0,0,0); n], Vec::new()),
+            ColorFormat::HdrF32 => (Vec::new(), vec![ColorF::rgb(0.0,0.0,0.0); n]),
+            ColorFormat::Depth  => (Vec::new(), Vec::new()),
+        };
         Self {
-            width, height,
-            color: vec![Color::rgb(0,0,0); width*height],
-            depth: vec![f32::INFINITY; width*height],
+            width, height, msaa, format,
+            color, color_hdr,
+            depth: vec![f32::INFINITY; n],
+            tone: ToneMapping::default(),
+            post: PostChain::default(),
+            resolved: Vec::new(),
         }
     }
 
+    /// Destino solo de profundidad, sin MSAA, sobre `depth` (`width*height` muestras) sin copiarla
+    pub fn depth_only(width: usize, height: usize, depth: Vec<f32>) -> Self {
+        assert_eq!(depth.len(), width*height, "depth_only: tamaño de depth");
+        Self { width, height, depth, ..Self::with_format(0, 0, Msaa::Off, ColorFormat::Depth) }
+    }
+
+    #[inline] pub fn is_hdr(&self) -> bool { self.format == ColorFormat::HdrF32 }
+
+    #[inline] pub fn samples(&self) -> usize { self.msaa.samples() }
+
+    /// Índice del píxel (no de la muestra)
     #[inline] pub fn idx(&self, x: i32, y: i32) -> Option<usize> {
         if x < 0 || y < 0 { return None; }
         let (x, y) = (x as usize, y as usize);
@@ -40,31 +168,99 @@ impl Framebuffer {
 
     pub fn clear_color(&mut self, c: Color) {
         self.color.fill(c);
+        self.color_hdr.fill(c.into());
     }
 
     pub fn clear_depth(&mut self) {
         self.depth.fill(f32::INFINITY);
     }
 
+    /// Escribe todas las muestras del píxel que pasan el test de z
     #[inline]
     pub fn put_pixel(&mut self, x: i32, y: i32, z: f32, c: Color) {
-        if let Some(i) = self.idx(x, y) {
-            if z < self.depth[i] {
-                self.depth[i] = z;
-                self.color[i] = c;
+        let Some(i) = self.idx(x, y) else { return };
+        let n = self.samples();
+        for s in i*n..(i+1)*n {
+            if z < self.depth[s] {
+                self.depth[s] = z;
+                match self.format {
+                    ColorFormat::Ldr8   => self.color[s] = c,
+                    ColorFormat::HdrF32 => self.color_hdr[s] = c.into(),
+                    ColorFormat::Depth  => {}
+                }
             }
         }
     }
 
-    pub fn save_png(&self, path: &str) -> Result<(), String> {
-        let mut img = RgbaImage::new(self.width as u32, self.height as u32);
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let i = y*self.width + x;
-                let px = self.color[i].to_rgba();
-                img.put_pixel(x as u32, y as u32, Rgba(px));
+    /// Un color de 8 bits por píxel: promedio de las muestras (box filter),
+    /// en HDR tone mapping, y la cadena de post-proceso. Sin MSAA, HDR ni post no copia.
+    pub fn resolve(&mut self) -> &[Color] {
+        if self.post.is_active() { return self.resolve_post(); }
+
+        let n = self.samples();
+        if self.is_hdr() {
+            self.resolved.resize(self.width*self.height, Color::default());
+            let inv = 1.0 / n as f32;
+            for (out, px) in self.resolved.iter_mut().zip(self.color_hdr.chunks_exact(n)) {
+                // Tone mapping por muestra antes de promediar: bordes brillantes sin aliasing
+                let mut acc = ColorF::rgba(0.0, 0.0, 0.0, 0.0);
+                for &c in px {
+                    let m = self.tone.map(c);
+                    acc.r += m.r; acc.g += m.g; acc.b += m.b; acc.a += m.a;
+                }
+                *out = ColorF::rgba(acc.r*inv, acc.g*inv, acc.b*inv, acc.a*inv).to_color();
             }
+            return &self.resolved;
         }
+        if n == 1 { return &self.color; }
+
+        self.resolved.resize(self.width*self.height, Color::default());
+        for (out, px) in self.resolved.iter_mut().zip(self.color.chunks_exact(n)) {
+            let (mut r, mut g, mut b, mut a) = (0u32, 0u32, 0u32, 0u32);
+            for c in px {
+                r += c.r as u32; g += c.g as u32; b += c.b as u32; a += c.a as u32;
+            }
+            let half = n as u32 / 2;
+            let n = n as u32;
+            *out = Color::rgba(((r + half)/n) as u8, ((g + half)/n) as u8, ((b + half)/n) as u8, ((a + half)/n) as u8);
+        }
+        &self.resolved
+    }
+
+    /// Con post-proceso: tone mapping por muestra y promedio, igual que sin post, y después
+    /// los pases de pantalla. Los pases HDR (bloom) corren sobre el promedio lineal y lo que
+    /// agregan se suma a cada muestra antes del tone mapping.
+    fn resolve_post(&mut self) -> &[Color] {
+        let glow = self.post.has_stage(PostStage::Hdr).then(|| {
+            let mut img = PostImage::from_framebuffer(self);
+            let base = img.color.clone();
+            self.post.run(PostStage::Hdr, &mut img);
+            img.color.iter().zip(&base)
+                .map(|(a, b)| ColorF::rgba(a.r - b.r, a.g - b.g, a.b - b.b, 0.0))
+                .collect::<Vec<_>>()
+        });
+
+        let tone = if self.is_hdr() { self.tone } else { ToneMapping { op: ToneMap::Clamp, exposure: 0.0 } };
+        let mut img = PostImage::tone_mapped(self, tone, glow.as_deref());
+        self.post.run(PostStage::Display, &mut img);
+
+        self.resolved.clear();
+        self.resolved.extend(img.color.iter().map(|c| c.to_color()));
+        &self.resolved
+    }
+
+    pub fn save_png(&mut self, path: &str) -> Result<(), String> {
+        let (width, height) = (self.width, self.height);
+        let img = to_image(width, height, self.resolve());
         img.save(path).map_err(|e| format!("No pude guardar '{}': {}", path, e))
     }
 }
+
+/// Copia píxeles resueltos (`width*height`, por filas) a una imagen RGBA
+pub fn to_image(width: usize, height: usize, pixels: &[Color]) -> RgbaImage {
+    let mut img = RgbaImage::new(width as u32, height as u32);
+    for (dst, c) in img.pixels_mut().zip(pixels) {
+        *dst = Rgba(c.to_rgba());
+    }
+    img
+}
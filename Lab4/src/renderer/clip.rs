@@ -0,0 +1,134 @@
+use crate::math::Vec4;
+
+use super::raster::Varyings;
+
+/// Vértice en clip space con sus varyings, listo para recortar
+#[derive(Copy, Clone, Debug)]
+pub struct ClipVertex {
+    pub clip: Vec4,
+    pub vary: Varyings,
+}
+
+/// Planos del frustum en clip space (OpenGL: -w <= x,y,z <= w).
+/// Distancia con signo: >= 0 dentro
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Plane { Near, Far, Left, Right, Bottom, Top }
+
+const PLANES: [Plane; 6] = [Plane::Near, Plane::Far, Plane::Left, Plane::Right, Plane::Bottom, Plane::Top];
+
+/// Margen mínimo en w para no dividir entre ~0 después del recorte
+const W_EPSILON: f32 = 1e-5;
+
+#[inline(always)]
+fn dist(p: Vec4, plane: Plane) -> f32 {
+    match plane {
+        Plane::Near   => p.z + p.w,
+        Plane::Far    => p.w - p.z,
+        Plane::Left   => p.x + p.w,
+        Plane::Right  => p.w - p.x,
+        Plane::Bottom => p.y + p.w,
+        Plane::Top    => p.w - p.y,
+    }
+}
+
+#[inline(always)]
+fn lerp_vertex(a: &ClipVertex, b: &ClipVertex, t: f32) -> ClipVertex {
+    // En clip space la interpolación lineal es correcta (antes de dividir por w)
+    ClipVertex {
+        clip: a.clip + (b.clip - a.clip) * t,
+        vary: Varyings {
+            pos_ws: a.vary.pos_ws + (b.vary.pos_ws - a.vary.pos_ws) * t,
+            nrm_ws: (a.vary.nrm_ws + (b.vary.nrm_ws - a.vary.nrm_ws) * t).normalize(),
+            uv:     a.vary.uv + (b.vary.uv - a.vary.uv) * t,
+            pos_os: a.vary.pos_os + (b.vary.pos_os - a.vary.pos_os) * t,
+        },
+    }
+}
+
+/// Sutherland–Hodgman contra un plano
+fn clip_against(input: &[ClipVertex], plane: Plane, out: &mut Vec<ClipVertex>) {
+    out.clear();
+    if input.is_empty() { return; }
+
+    let mut prev = input[input.len() - 1];
+    let mut d_prev = dist(prev.clip, plane);
+    for &cur in input {
+        let d_cur = dist(cur.clip, plane);
+        if d_cur >= 0.0 {
+            if d_prev < 0.0 {
+                out.push(lerp_vertex(&prev, &cur, d_prev / (d_prev - d_cur)));
+            }
+            out.push(cur);
+        } else if d_prev >= 0.0 {
+            out.push(lerp_vertex(&prev, &cur, d_prev / (d_prev - d_cur)));
+        }
+        prev = cur;
+        d_prev = d_cur;
+    }
+}
+
+/// Recorta un triángulo contra los 6 planos del frustum.
+/// Devuelve un polígono convexo (0 o >= 3 vértices) para triangular en abanico.
+pub fn clip_triangle(tri: [ClipVertex; 3]) -> Vec<ClipVertex> {
+    // Aceptación trivial: todo dentro
+    let all_inside = tri.iter().all(|v| PLANES.iter().all(|&pl| dist(v.clip, pl) >= 0.0));
+    if all_inside && tri.iter().all(|v| v.clip.w > W_EPSILON) {
+        return tri.to_vec();
+    }
+
+    // Rechazo trivial: todo fuera de un mismo plano
+    if PLANES.iter().any(|&pl| tri.iter().all(|v| dist(v.clip, pl) < 0.0)) {
+        return Vec::new();
+    }
+
+    let mut poly = tri.to_vec();
+    let mut tmp = Vec::with_capacity(9);
+    for &pl in &PLANES {
+        clip_against(&poly, pl, &mut tmp);
+        std::mem::swap(&mut poly, &mut tmp);
+        if poly.len() < 3 { return Vec::new(); }
+    }
+
+    // Tras recortar near, w >= near > 0; filtramos degenerados por precisión
+    if poly.iter().any(|v| v.clip.w <= W_EPSILON) { return Vec::new(); }
+    poly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Vec2, Vec3};
+
+    fn cv(x: f32, y: f32, z: f32, w: f32) -> ClipVertex {
+        ClipVertex {
+            clip: Vec4::new(x, y, z, w),
+            vary: Varyings { pos_ws: Vec3::new(x, y, z), nrm_ws: Vec3::new(0.0, 0.0, 1.0), uv: Vec2::new(x, y), pos_os: Vec3::new(x, y, z) },
+        }
+    }
+
+    #[test]
+    fn inside_triangle_untouched() {
+        let tri = [cv(0.0, 0.0, 0.0, 1.0), cv(0.5, 0.0, 0.0, 1.0), cv(0.0, 0.5, 0.0, 1.0)];
+        let poly = clip_triangle(tri);
+        assert_eq!(poly.len(), 3);
+        assert_eq!(poly[1].clip, tri[1].clip);
+    }
+
+    #[test]
+    fn behind_camera_rejected() {
+        let tri = [cv(0.0, 0.0, 2.0, -1.0), cv(0.5, 0.0, 2.0, -1.0), cv(0.0, 0.5, 2.0, -1.0)];
+        assert!(clip_triangle(tri).is_empty());
+    }
+
+    #[test]
+    fn near_plane_split() {
+        // Un vértice detrás del plano near -> quad
+        let tri = [cv(0.0, 0.0, -2.0, 1.0), cv(0.5, 0.0, 0.0, 1.0), cv(0.0, 0.5, 0.0, 1.0)];
+        let poly = clip_triangle(tri);
+        assert_eq!(poly.len(), 4);
+        for v in &poly {
+            assert!(v.clip.z + v.clip.w >= -1e-5);
+            assert!(v.clip.w > 0.0);
+        }
+    }
+}
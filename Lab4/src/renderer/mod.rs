@@ -1,8 +1,22 @@
 pub mod uniforms;
 pub mod buffers;
+pub mod tonemap;
+pub mod post;
 pub mod raster;
+pub mod tiles;
+mod clip;
+pub mod blend;
 pub mod pipeline;
-
+pub mod queue;
+pub mod shadow;
 
 pub use uniforms::{Uniforms, PlanetParams};
-pub use buffers::Framebuffer;
+pub use buffers::{Framebuffer, FramebufferSettings, Color, ColorF, ColorFormat, Msaa};
+pub use tonemap::{ToneMap, ToneMapping};
+pub use post::{PostChain, PostPass, PostStage, PostImage};
+pub use raster::{Varyings, DrawStats};
+pub use tiles::RasterMode;
+pub use blend::{BlendState, BlendFactor};
+pub use pipeline::{Shader, PipelineState, CullMode, VertexIn, VertexOut, draw_mesh, bake_mesh};
+pub use queue::RenderQueue;
+pub use shadow::{ShadowSettings, ShadowMap};
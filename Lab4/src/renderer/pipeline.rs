@@ -1,7 +1,10 @@
 use crate::math::{Vec2, Vec3, Vec4, Mat4};
 use crate::scene::Mesh;
-use super::buffers::{Framebuffer, Color};
-use super::raster::{RasterInput, Varyings, raster_triangle};
+use super::buffers::{Framebuffer, ColorF};
+use super::raster::{RasterInput, RasterTarget, PixelRect, Varyings, FragmentOps, DrawStats, raster_triangle, triangle_bounds};
+use super::tiles::{RasterMode, raster_tiled};
+use super::blend::BlendState;
+use super::clip::{ClipVertex, clip_triangle};
 use super::uniforms::Uniforms;
 
 /// Entrada al vertex shader
@@ -10,6 +13,8 @@ pub struct VertexIn {
     pub pos: Vec3,
     pub nrm: Vec3,
     pub uv:  Vec2, 
+    /// Tangente de la malla (`w` = sentido de la bitangente)
+    pub tangent: Vec4,
 }
 
 /// Salida del vertex shader
@@ -19,17 +24,80 @@ pub struct VertexOut {
     pub pos_ws: Vec3,  
     pub nrm_ws: Vec3,
     pub uv: Vec2,
+    /// Posición en espacio objeto, tras lo que desplace el vertex shader
+    pub pos_os: Vec3,
 }
 
+/// Qué caras descartar según su winding en pantalla
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CullMode {
+    #[default]
+    None,
+    Back,
+    Front,
+}
+
+/// Estado fijo del pipeline, elegido por draw call
+#[derive(Copy, Clone, Debug)]
+pub struct PipelineState {
+    pub cull: CullMode,
+    /// Test de profundidad antes del fragment shader (si el shader lo permite)
+    pub early_z: bool,
+    pub depth_write: bool,
+    /// `false` para pases solo de profundidad (sombras)
+    pub color_write: bool,
+    /// Descarta fragmentos con alpha menor al valor dado
+    pub alpha_test: Option<f32>,
+    pub blend: BlendState,
+    pub raster: RasterMode,
+    /// Participa del pase de sombras de la `RenderQueue`
+    pub cast_shadow: bool,
+}
+
+impl Default for PipelineState {
+    fn default() -> Self {
+        Self {
+            cull: CullMode::None, early_z: true, depth_write: true, color_write: true, alpha_test: None,
+            blend: BlendState::OPAQUE, raster: RasterMode::default(), cast_shadow: true,
+        }
+    }
+}
+
+impl PipelineState {
+    /// Estado típico de un pase transparente: test de z sin escribir, mezcla "over"
+    pub fn transparent() -> Self {
+        Self { depth_write: false, blend: BlendState::ALPHA, ..Default::default() }
+    }
+
+    pub fn is_transparent(&self) -> bool { !self.blend.is_opaque() }
+}
 
-pub trait Shader {
+impl CullMode {
+    /// `area` es el doble del área con signo en pantalla (y hacia abajo).
+    /// Las caras frontales son CCW en NDC, que tras el viewport quedan con área negativa.
+    #[inline]
+    pub fn culls(self, area: f32) -> bool {
+        match self {
+            CullMode::None  => area == 0.0,
+            CullMode::Back  => area >= 0.0,
+            CullMode::Front => area <= 0.0,
+        }
+    }
+}
+
+/// `Send + Sync` porque el rasterizador por tiles comparte el shader entre hilos
+pub trait Shader: Send + Sync {
     fn name(&self) -> &'static str { "UnnamedShader" }
 
     /// Vertex: recibe atributos por-vértice + uniforms, devuelve clip_pos y varyings
     fn vertex(&mut self, vin: VertexIn, uniforms: &Uniforms) -> VertexOut;
 
-    /// Fragment: recibe varyings interpolados + uniforms y devuelve Color
-    fn fragment(&mut self, vary: &Varyings, uniforms: &Uniforms) -> Color;
+    /// Fragment: recibe varyings interpolados + uniforms y devuelve radiancia lineal
+    /// (puede pasar de 1; se recorta solo si el destino es LDR)
+    fn fragment(&self, vary: &Varyings, uniforms: &Uniforms) -> ColorF;
+
+    /// `false` si el fragment modifica la profundidad o descarta: desactiva early-z
+    fn early_depth(&self) -> bool { true }
 }
 
 pub fn draw_mesh(
@@ -38,62 +106,166 @@ pub fn draw_mesh(
     shader: &mut dyn Shader,
     uniforms: &Uniforms,
     viewport: Mat4,
-) {
+    state: &PipelineState,
+) -> DrawStats {
     let _mvp = uniforms.proj * uniforms.view * uniforms.model;
 
     // Vertex stage
     let mut clip_positions: Vec<Vec4> = Vec::with_capacity(mesh.vertices.len());
-    let mut vary_buff: Vec<(Vec3, Vec3, Vec2)> = Vec::with_capacity(mesh.vertices.len());
+    let mut vary_buff: Vec<Varyings> = Vec::with_capacity(mesh.vertices.len());
 
     for v in &mesh.vertices {
         let vin = VertexIn {
             pos: v.pos,
             nrm: v.nrm,
-            uv:  Vec2::new(0.0, 0.0),
+            uv:  v.uv,
+            tangent: v.tangent,
         };
         let vout = shader.vertex(vin, uniforms);
         clip_positions.push(vout.clip_pos);
-        vary_buff.push((vout.pos_ws, vout.nrm_ws, vout.uv));
+        vary_buff.push(Varyings { pos_ws: vout.pos_ws, nrm_ws: vout.nrm_ws.normalize(), uv: vout.uv, pos_os: vout.pos_os });
     }
 
+    let ops = FragmentOps {
+        early_z: state.early_z && shader.early_depth(),
+        depth_write: state.depth_write,
+        color_write: state.color_write,
+        alpha_cutoff: state.alpha_test,
+        blend: state.blend,
+    };
+    let mut stats = DrawStats::default();
+
+    // Setup: triángulos en pantalla que sobreviven recorte, culling y viewport
+    let mut screen_tris: Vec<(RasterInput, PixelRect)> = Vec::with_capacity(mesh.indices.len());
     for tri in &mesh.indices {
         let idx = [tri.i0 as usize, tri.i1 as usize, tri.i2 as usize];
 
-        // Clip coordinates
-        let cp = [clip_positions[idx[0]], clip_positions[idx[1]], clip_positions[idx[2]]];
-
-        if cp.iter().any(|p| p.w <= 0.0) { continue; }
-
-        // NDC
-        let ndc = [
-            Vec4::new(cp[0].x/cp[0].w, cp[0].y/cp[0].w, cp[0].z/cp[0].w, 1.0),
-            Vec4::new(cp[1].x/cp[1].w, cp[1].y/cp[1].w, cp[1].z/cp[1].w, 1.0),
-            Vec4::new(cp[2].x/cp[2].w, cp[2].y/cp[2].w, cp[2].z/cp[2].w, 1.0),
-        ];
-
-        // Viewport 
-        let sp = [
-            viewport * ndc[0],
-            viewport * ndc[1],
-            viewport * ndc[2],
-        ];
-
-        let z = [sp[0].z, sp[1].z, sp[2].z];
-        let inv_w = [1.0/cp[0].w, 1.0/cp[1].w, 1.0/cp[2].w];
-
-        // Varyings
-        let v0 = vary_buff[idx[0]];
-        let v1 = vary_buff[idx[1]];
-        let v2 = vary_buff[idx[2]];
-        let v = [
-            Varyings { pos_ws: v0.0, nrm_ws: v0.1.normalize(), uv: v0.2 },
-            Varyings { pos_ws: v1.0, nrm_ws: v1.1.normalize(), uv: v1.2 },
-            Varyings { pos_ws: v2.0, nrm_ws: v2.1.normalize(), uv: v2.2 },
-        ];
-
-        let rin = RasterInput { p: sp, z, inv_w, v };
-        raster_triangle(fb, &rin, |_x, _y, _z, vary| {
-            shader.fragment(&vary, uniforms)
-        });
+        // Clip coordinates + varyings
+        let corner = |i: usize| ClipVertex { clip: clip_positions[i], vary: vary_buff[i] };
+
+        // Recorte contra el frustum (near incluido) y triangulación en abanico
+        let poly = clip_triangle([corner(idx[0]), corner(idx[1]), corner(idx[2])]);
+        for i in 1..poly.len().saturating_sub(1) {
+            let rin = setup_triangle([poly[0], poly[i], poly[i + 1]], viewport);
+            if state.cull.culls(signed_area(&rin)) { continue; }
+            if let Some(bounds) = triangle_bounds(&rin, fb.width, fb.height) {
+                screen_tris.push((rin, bounds));
+            }
+        }
+    }
+    stats.triangles = screen_tris.len() as u32;
+
+    let shader: &dyn Shader = &*shader;
+    let shade = |_x: i32, _y: i32, _z: f32, vary: Varyings| shader.fragment(&vary, uniforms);
+    match state.raster {
+        RasterMode::Single => {
+            let mut target = RasterTarget::full(fb);
+            for (rin, bounds) in &screen_tris {
+                raster_triangle(&mut target, *bounds, rin, ops, &mut stats, shade);
+            }
+        }
+        RasterMode::Tiled { tile_size, threads } => {
+            raster_tiled(fb, &screen_tris, ops, tile_size, threads, &mut stats, &shade);
+        }
+    }
+    stats
+}
+
+/// Hornea un shader en la malla: posiciones y normales salen del vertex shader (en mundo,
+/// con el desplazamiento que aplique) y el color por vértice, del fragment evaluado en cada
+/// vértice y recortado a 0..1. UV, triángulos, partes y materiales se conservan.
+pub fn bake_mesh(mesh: &Mesh, shader: &mut dyn Shader, uniforms: &Uniforms) -> Mesh {
+    let mut out = mesh.clone();
+    out.colors = Vec::with_capacity(mesh.vertices.len());
+    for v in &mut out.vertices {
+        let vout = shader.vertex(VertexIn { pos: v.pos, nrm: v.nrm, uv: v.uv, tangent: v.tangent }, uniforms);
+        let vary = Varyings { pos_ws: vout.pos_ws, nrm_ws: vout.nrm_ws.normalize(), uv: vout.uv, pos_os: vout.pos_os };
+        let c = shader.fragment(&vary, uniforms);
+        v.pos = vary.pos_ws;
+        v.nrm = vary.nrm_ws;
+        out.colors.push(Vec3::new(c.r, c.g, c.b).clamp01());
     }
-}
\ No newline at end of file
+    out.recompute_tangents();
+    out
+}
+
+/// Doble del área con signo del triángulo ya en pantalla
+#[inline]
+fn signed_area(t: &RasterInput) -> f32 {
+    let (a, b, c) = (t.p[0], t.p[1], t.p[2]);
+    (b.x - a.x)*(c.y - a.y) - (b.y - a.y)*(c.x - a.x)
+}
+
+/// Divide por w y aplica viewport
+fn setup_triangle(tri: [ClipVertex; 3], viewport: Mat4) -> RasterInput {
+    let cp = [tri[0].clip, tri[1].clip, tri[2].clip];
+
+    // NDC
+    let ndc = [
+        Vec4::new(cp[0].x/cp[0].w, cp[0].y/cp[0].w, cp[0].z/cp[0].w, 1.0),
+        Vec4::new(cp[1].x/cp[1].w, cp[1].y/cp[1].w, cp[1].z/cp[1].w, 1.0),
+        Vec4::new(cp[2].x/cp[2].w, cp[2].y/cp[2].w, cp[2].z/cp[2].w, 1.0),
+    ];
+
+    // Viewport 
+    let sp = [
+        viewport * ndc[0],
+        viewport * ndc[1],
+        viewport * ndc[2],
+    ];
+
+    let z = [sp[0].z, sp[1].z, sp[2].z];
+    let inv_w = [1.0/cp[0].w, 1.0/cp[1].w, 1.0/cp[2].w];
+    let v = [tri[0].vary, tri[1].vary, tri[2].vary];
+
+    RasterInput { p: sp, z, inv_w, v }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::mat::translate;
+    use crate::utils::testing::{looking_at_origin, sphere};
+
+    /// Color constante; `early` decide si acepta early-z
+    struct Solid { color: ColorF, early: bool }
+
+    impl Shader for Solid {
+        fn vertex(&mut self, vin: VertexIn, u: &Uniforms) -> VertexOut {
+            let pos_ws = (u.model * Vec4::from3(vin.pos, 1.0)).xyz();
+            VertexOut { clip_pos: u.proj * u.view * Vec4::from3(pos_ws, 1.0), pos_ws, nrm_ws: vin.nrm, uv: vin.uv, pos_os: vin.pos }
+        }
+
+        fn fragment(&self, _vary: &Varyings, _u: &Uniforms) -> ColorF { self.color }
+
+        fn early_depth(&self) -> bool { self.early }
+    }
+
+    #[test]
+    fn late_depth_shades_hidden_fragments_but_keeps_the_nearest() {
+        let mesh = sphere();
+        let (u, vp) = looking_at_origin(Vec3::new(0.0, 0.0, 3.0), 48, 48, 0.0);
+        let behind = Uniforms { model: translate(Vec3::new(0.0, 0.0, -2.0)), ..u.clone() };
+        let state = PipelineState::default();
+
+        // Primero la esfera cercana (verde), después una detrás (roja) que queda tapada
+        let render = |early: bool| {
+            let mut fb = Framebuffer::new(48, 48);
+            draw_mesh(&mut fb, &mesh, &mut Solid { color: ColorF::rgb(0.0, 1.0, 0.0), early: true }, &u, vp, &state);
+            let stats = draw_mesh(&mut fb, &mesh, &mut Solid { color: ColorF::rgb(1.0, 0.0, 0.0), early }, &behind, vp, &state);
+            (stats, fb.depth.clone(), fb.resolve().iter().map(|c| c.to_rgba()).collect::<Vec<_>>())
+        };
+        let (early, early_depth, early_color) = render(true);
+        let (late, late_depth, late_color) = render(false);
+
+        assert!(early.fragments_early_rejected > 0);
+        assert_eq!(late.fragments_early_rejected, 0);
+        // Sin early-z se sombrean también los fragmentos tapados...
+        assert_eq!(late.fragments_shaded, early.fragments_shaded + early.fragments_early_rejected);
+        // ...pero el test de z posterior deja la misma imagen
+        assert_eq!(late_depth, early_depth);
+        assert_eq!(late_color, early_color);
+        assert_eq!(late_color[24 * 48 + 24], [0, 255, 0, 255]);
+    }
+}
@@ -0,0 +1,120 @@
+use super::{luma, PostImage, PostPass, PostStage};
+use crate::renderer::buffers::ColorF;
+
+/// Resplandor alrededor de lo que supera `threshold`: se extrae lo brillante a
+/// media resolución, se difumina con un gaussiano separable y se suma.
+/// Corre antes del tone mapping, así en HDR el emisivo de Lava/SciFi "sangra".
+#[derive(Copy, Clone, Debug)]
+pub struct Bloom {
+    /// Luminancia a partir de la cual un píxel brilla
+    pub threshold: f32,
+    /// Ancho de la rodilla suave alrededor del umbral
+    pub knee: f32,
+    pub intensity: f32,
+    /// Radio del desenfoque en píxeles de media resolución
+    pub radius: usize,
+}
+
+impl Default for Bloom {
+    fn default() -> Self { Self { threshold: 0.8, knee: 0.3, intensity: 0.6, radius: 6 } }
+}
+
+impl Bloom {
+    /// Peso de un píxel según su luminancia, con transición suave en el umbral
+    fn bright(&self, c: ColorF) -> ColorF {
+        let l = luma(c);
+        if l <= 0.0 { return ColorF::rgba(0.0, 0.0, 0.0, 0.0); }
+        let soft = (l - self.threshold + self.knee).clamp(0.0, 2.0*self.knee);
+        let soft = soft*soft / (4.0*self.knee + 1e-4);
+        let k = soft.max(l - self.threshold) / l;
+        ColorF::rgb(c.r*k, c.g*k, c.b*k)
+    }
+}
+
+fn gaussian_kernel(radius: usize) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+    let mut k: Vec<f32> = (0..=radius)
+        .map(|i| (-((i*i) as f32) / (2.0*sigma*sigma)).exp())
+        .collect();
+    let sum = k[0] + 2.0*k[1..].iter().sum::<f32>();
+    for w in &mut k { *w /= sum; }
+    k
+}
+
+/// Un paso del desenfoque separable (`(dx, dy)` = dirección), borde replicado
+fn blur(src: &[ColorF], w: usize, h: usize, kernel: &[f32], dx: i32, dy: i32) -> Vec<ColorF> {
+    let at = |x: i32, y: i32| {
+        src[y.clamp(0, h as i32 - 1) as usize * w + x.clamp(0, w as i32 - 1) as usize]
+    };
+    let mut out = vec![ColorF::rgba(0.0, 0.0, 0.0, 0.0); w*h];
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let c = at(x, y);
+            let mut acc = ColorF::rgb(c.r*kernel[0], c.g*kernel[0], c.b*kernel[0]);
+            for (i, &k) in kernel.iter().enumerate().skip(1) {
+                let i = i as i32;
+                let (a, b) = (at(x + dx*i, y + dy*i), at(x - dx*i, y - dy*i));
+                acc.r += (a.r + b.r)*k;
+                acc.g += (a.g + b.g)*k;
+                acc.b += (a.b + b.b)*k;
+            }
+            out[y as usize * w + x as usize] = acc;
+        }
+    }
+    out
+}
+
+impl PostPass for Bloom {
+    fn name(&self) -> &'static str { "bloom" }
+
+    fn stage(&self) -> PostStage { PostStage::Hdr }
+
+    fn apply(&self, img: &mut PostImage, _frame: u64) {
+        let (w, h) = (img.width, img.height);
+        if w == 0 || h == 0 { return; }
+        let (hw, hh) = (w.div_ceil(2), h.div_ceil(2));
+
+        // Extracción + downsample 2x2
+        let mut half = vec![ColorF::rgba(0.0, 0.0, 0.0, 0.0); hw*hh];
+        for (i, out) in half.iter_mut().enumerate() {
+            let (x, y) = ((i % hw) as i32 * 2, (i / hw) as i32 * 2);
+            let mut acc = ColorF::rgb(0.0, 0.0, 0.0);
+            for (ox, oy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let b = self.bright(img.get(x + ox, y + oy));
+                acc.r += b.r*0.25; acc.g += b.g*0.25; acc.b += b.b*0.25;
+            }
+            *out = acc;
+        }
+
+        let kernel = gaussian_kernel(self.radius.max(1));
+        let half = blur(&half, hw, hh, &kernel, 1, 0);
+        let half = blur(&half, hw, hh, &kernel, 0, 1);
+        let glow = PostImage { width: hw, height: hh, color: half };
+
+        // Upsample bilineal y suma
+        for (i, c) in img.color.iter_mut().enumerate() {
+            let (x, y) = ((i % w) as f32 + 0.5, (i / w) as f32 + 0.5);
+            let g = glow.sample(x * 0.5, y * 0.5);
+            c.r += g.r*self.intensity;
+            c.g += g.g*self.intensity;
+            c.b += g.b*self.intensity;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bright_spot_glows_dark_stays() {
+        let (w, h) = (48, 48);
+        let mut color = vec![ColorF::rgb(0.1, 0.1, 0.1); w*h];
+        color[40*w + 40] = ColorF::rgb(8.0, 4.0, 1.0);
+        let mut img = PostImage { width: w, height: h, color };
+        Bloom::default().apply(&mut img, 0);
+
+        assert!(img.color[40*w + 36].r > 0.1);
+        assert_eq!(img.color[0], ColorF::rgb(0.1, 0.1, 0.1));
+    }
+}
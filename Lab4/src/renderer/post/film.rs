@@ -0,0 +1,65 @@
+use super::{luma, PostImage, PostPass};
+
+/// Oscurece hacia las esquinas
+#[derive(Copy, Clone, Debug)]
+pub struct Vignette {
+    /// 0 = sin efecto, 1 = esquinas negras
+    pub strength: f32,
+    /// Distancia (0 centro, 1 esquina) donde empieza a oscurecer
+    pub inner: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self { Self { strength: 0.45, inner: 0.35 } }
+}
+
+impl PostPass for Vignette {
+    fn name(&self) -> &'static str { "vignette" }
+
+    fn apply(&self, img: &mut PostImage, _frame: u64) {
+        let (w, h) = (img.width as f32, img.height as f32);
+        let half_diag = (w*w + h*h).sqrt() * 0.5;
+        for (i, c) in img.color.iter_mut().enumerate() {
+            let x = (i % img.width) as f32 + 0.5 - w*0.5;
+            let y = (i / img.width) as f32 + 0.5 - h*0.5;
+            let d = (x*x + y*y).sqrt() / half_diag;
+            let t = ((d - self.inner) / (1.0 - self.inner)).clamp(0.0, 1.0);
+            let k = 1.0 - self.strength * t*t*(3.0 - 2.0*t);
+            c.r *= k; c.g *= k; c.b *= k;
+        }
+    }
+}
+
+/// Grano de película: ruido por píxel que cambia con `frame`, más visible en sombras
+#[derive(Copy, Clone, Debug)]
+pub struct Grain {
+    pub strength: f32,
+}
+
+impl Default for Grain {
+    fn default() -> Self { Self { strength: 0.05 } }
+}
+
+/// Hash entero -> [0,1)
+#[inline]
+fn hash(x: u32, y: u32, frame: u32) -> f32 {
+    let mut h = x.wrapping_mul(0x8da6_b343) ^ y.wrapping_mul(0xd816_3841) ^ frame.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+impl PostPass for Grain {
+    fn name(&self) -> &'static str { "grain" }
+
+    fn apply(&self, img: &mut PostImage, frame: u64) {
+        for (i, c) in img.color.iter_mut().enumerate() {
+            let n = hash((i % img.width) as u32, (i / img.width) as u32, frame as u32) - 0.5;
+            let k = n * self.strength * (1.0 - 0.5*luma(*c).clamp(0.0, 1.0));
+            c.r += k; c.g += k; c.b += k;
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::{luma, PostImage, PostPass};
+use crate::renderer::buffers::ColorF;
+
+/// FXAA (variante "consola" de Lottes): estima la dirección del borde con la
+/// luma de las diagonales y promedia a lo largo de él. Suaviza lo que MSAA no
+/// cubre (bordes dentro de un mismo triángulo, texturas procedurales).
+#[derive(Copy, Clone, Debug)]
+pub struct Fxaa {
+    /// Largo máximo del filtro, en píxeles
+    pub span_max: f32,
+    pub reduce_mul: f32,
+    pub reduce_min: f32,
+}
+
+impl Default for Fxaa {
+    fn default() -> Self { Self { span_max: 8.0, reduce_mul: 1.0 / 8.0, reduce_min: 1.0 / 128.0 } }
+}
+
+impl PostPass for Fxaa {
+    fn name(&self) -> &'static str { "fxaa" }
+
+    fn apply(&self, img: &mut PostImage, _frame: u64) {
+        let (w, h) = (img.width, img.height);
+        let mut out = img.color.clone();
+        let l = |x: i32, y: i32| luma(img.get(x, y));
+
+        for y in 0..h as i32 {
+            for x in 0..w as i32 {
+                let (nw, ne, sw, se, m) = (l(x - 1, y - 1), l(x + 1, y - 1), l(x - 1, y + 1), l(x + 1, y + 1), l(x, y));
+                let lmin = m.min(nw.min(ne).min(sw.min(se)));
+                let lmax = m.max(nw.max(ne).max(sw.max(se)));
+                if lmax - lmin < (lmax * 0.125).max(1.0 / 32.0) { continue; }
+
+                let mut dx = -((nw + ne) - (sw + se));
+                let mut dy = (nw + sw) - (ne + se);
+                let reduce = ((nw + ne + sw + se) * 0.25 * self.reduce_mul).max(self.reduce_min);
+                let rcp = 1.0 / (dx.abs().min(dy.abs()) + reduce);
+                dx = (dx * rcp).clamp(-self.span_max, self.span_max);
+                dy = (dy * rcp).clamp(-self.span_max, self.span_max);
+
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let tap = |t: f32| img.sample(cx + dx*t, cy + dy*t);
+                let avg = |a: ColorF, b: ColorF| ColorF::rgba((a.r + b.r)*0.5, (a.g + b.g)*0.5, (a.b + b.b)*0.5, (a.a + b.a)*0.5);
+
+                let a = avg(tap(1.0/3.0 - 0.5), tap(2.0/3.0 - 0.5));
+                let b = avg(a, avg(tap(-0.5), tap(0.5)));
+                let lb = luma(b);
+                out[y as usize * w + x as usize] = if lb < lmin || lb > lmax { a } else { b };
+            }
+        }
+        img.color = out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn softens_hard_diagonal() {
+        let (w, h) = (16, 16);
+        let color = (0..w*h)
+            .map(|i| if i % w > i / w { ColorF::rgb(1.0, 1.0, 1.0) } else { ColorF::rgb(0.0, 0.0, 0.0) })
+            .collect();
+        let mut img = PostImage { width: w, height: h, color };
+        Fxaa::default().apply(&mut img, 0);
+
+        let grey = img.color.iter().filter(|c| c.r > 0.05 && c.r < 0.95).count();
+        assert!(grey > 0);
+        assert_eq!(img.color[2*w + 12].r, 1.0);
+    }
+}
@@ -0,0 +1,173 @@
+use super::{PostImage, PostPass};
+use crate::renderer::buffers::ColorF;
+
+/// LUT 3D de corrección de color (formato `.cube`), con interpolación trilineal.
+/// Se aplica sobre el color de pantalla, en [0,1].
+#[derive(Clone, Debug)]
+pub struct ColorLut {
+    size: usize,
+    /// `size³` entradas, con r variando más rápido (orden de `.cube`)
+    data: Vec<[f32; 3]>,
+    /// Rango de entrada (`DOMAIN_MIN`/`DOMAIN_MAX`) que cubre la tabla
+    domain: ([f32; 3], [f32; 3]),
+    /// Mezcla con el color original (1 = LUT completa)
+    pub strength: f32,
+}
+
+impl ColorLut {
+    /// LUT construida evaluando `f` en cada nodo
+    pub fn from_fn(size: usize, f: impl Fn([f32; 3]) -> [f32; 3]) -> Self {
+        let size = size.max(2);
+        let s = (size - 1) as f32;
+        let mut data = Vec::with_capacity(size*size*size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.push(f([r as f32 / s, g as f32 / s, b as f32 / s]));
+                }
+            }
+        }
+        Self { size, data, domain: ([0.0; 3], [1.0; 3]), strength: 1.0 }
+    }
+
+    /// Presets: `warm`, `cool`, `bleach` (bleach bypass, desaturado y contrastado)
+    pub fn preset(name: &str) -> Option<Self> {
+        let lut = match name {
+            "warm" => Self::from_fn(17, |[r, g, b]| [r*1.08 + 0.02, g*1.01, b*0.88]),
+            "cool" => Self::from_fn(17, |[r, g, b]| [r*0.9, g*1.0 + 0.01, b*1.1 + 0.03]),
+            "bleach" => Self::from_fn(17, |[r, g, b]| {
+                let l = 0.2126*r + 0.7152*g + 0.0722*b;
+                let s = |c: f32| {
+                    let c = l + (c - l)*0.55;
+                    let t = c.clamp(0.0, 1.0);
+                    t*t*(3.0 - 2.0*t)
+                };
+                [s(r), s(g), s(b)]
+            }),
+            _ => return None,
+        };
+        Some(lut)
+    }
+
+    /// Preset por nombre o, si no existe, archivo `.cube`
+    pub fn preset_or_file(arg: &str) -> Result<Self, String> {
+        match Self::preset(arg) {
+            Some(l) => Ok(l),
+            None => Self::load(arg),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        Self::parse_cube(&text).map_err(|e| format!("{path}: {e}"))
+    }
+
+    /// Lee un `.cube` (Adobe/Resolve): `LUT_3D_SIZE`, `DOMAIN_MIN/MAX` opcionales y tripletas
+    pub fn parse_cube(text: &str) -> Result<Self, String> {
+        let mut size = 0usize;
+        let (mut lo, mut hi) = ([0.0f32; 3], [1.0f32; 3]);
+        let mut data = Vec::new();
+
+        let triple = |it: &mut dyn Iterator<Item = &str>, n: usize| -> Result<[f32; 3], String> {
+            let mut v = [0.0; 3];
+            for x in &mut v {
+                *x = it.next().and_then(|t| t.parse().ok())
+                    .ok_or_else(|| format!("línea {n}: se esperaban 3 números"))?;
+            }
+            Ok(v)
+        };
+
+        for (n, line) in text.lines().enumerate() {
+            let n = n + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let mut it = line.split_whitespace();
+            match it.next() {
+                Some("TITLE") => {}
+                Some("LUT_1D_SIZE") => return Err(format!("línea {n}: LUTs 1D no soportadas")),
+                Some("LUT_3D_SIZE") => {
+                    size = it.next().and_then(|t| t.parse().ok())
+                        .filter(|&s| (2..=256).contains(&s))
+                        .ok_or_else(|| format!("línea {n}: LUT_3D_SIZE inválido"))?;
+                }
+                Some("DOMAIN_MIN") => lo = triple(&mut it, n)?,
+                Some("DOMAIN_MAX") => hi = triple(&mut it, n)?,
+                Some(_) => data.push(triple(&mut line.split_whitespace(), n)?),
+                None => {}
+            }
+        }
+        if size == 0 { return Err("falta LUT_3D_SIZE".into()); }
+        if data.len() != size*size*size {
+            return Err(format!("se esperaban {} entradas, hay {}", size*size*size, data.len()));
+        }
+        // El dominio es el rango de entrada: las entradas de la tabla ya son colores de salida
+        if (0..3).any(|k| hi[k] <= lo[k]) { return Err("DOMAIN_MAX debe ser mayor que DOMAIN_MIN".into()); }
+        Ok(Self { size, data, domain: (lo, hi), strength: 1.0 })
+    }
+
+    pub fn lookup(&self, c: [f32; 3]) -> [f32; 3] {
+        let s = (self.size - 1) as f32;
+        let (lo, hi) = self.domain;
+        let x: [f32; 3] = std::array::from_fn(|k| ((c[k] - lo[k]) / (hi[k] - lo[k])).clamp(0.0, 1.0) * s);
+        let i0 = x.map(|v| (v as usize).min(self.size - 2));
+        let t: [f32; 3] = std::array::from_fn(|k| x[k] - i0[k] as f32);
+        let at = |r: usize, g: usize, b: usize| self.data[(b*self.size + g)*self.size + r];
+        let mut out = [0.0f32; 3];
+        for (corner, w) in (0..8).map(|k| {
+            let (dr, dg, db) = (k & 1, (k >> 1) & 1, (k >> 2) & 1);
+            let w = (if dr == 1 { t[0] } else { 1.0 - t[0] })
+                * (if dg == 1 { t[1] } else { 1.0 - t[1] })
+                * (if db == 1 { t[2] } else { 1.0 - t[2] });
+            (at(i0[0] + dr, i0[1] + dg, i0[2] + db), w)
+        }) {
+            for (o, v) in out.iter_mut().zip(corner) { *o += v * w; }
+        }
+        out
+    }
+}
+
+impl PostPass for ColorLut {
+    fn name(&self) -> &'static str { "lut" }
+
+    fn apply(&self, img: &mut PostImage, _frame: u64) {
+        let k = self.strength;
+        for c in &mut img.color {
+            let [r, g, b] = self.lookup([c.r, c.g, c.b]);
+            *c = ColorF::rgba(c.r + (r - c.r)*k, c.g + (g - c.g)*k, c.b + (b - c.b)*k, c.a);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cube_roundtrip_identity() {
+        let mut text = String::from("# test\nLUT_3D_SIZE 2\n");
+        for i in 0..8 {
+            text += &format!("{} {} {}\n", i & 1, (i >> 1) & 1, (i >> 2) & 1);
+        }
+        let lut = ColorLut::parse_cube(&text).unwrap();
+        let out = lut.lookup([0.25, 0.5, 0.9]);
+        assert!((out[0] - 0.25).abs() < 1e-5 && (out[1] - 0.5).abs() < 1e-5 && (out[2] - 0.9).abs() < 1e-5);
+
+        let err = ColorLut::parse_cube("LUT_3D_SIZE 2\n0 0 0\n1 x 0\n").unwrap_err();
+        assert!(err.contains("línea 3"));
+    }
+
+    #[test]
+    fn domain_remaps_the_input() {
+        // Identidad sobre [0,2]: la entrada 1 cae en el medio de la tabla y sale 1
+        let mut text = String::from("LUT_3D_SIZE 2\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\n");
+        for i in 0..8 {
+            text += &format!("{} {} {}\n", 2 * (i & 1), 2 * ((i >> 1) & 1), 2 * ((i >> 2) & 1));
+        }
+        let lut = ColorLut::parse_cube(&text).unwrap();
+        let out = lut.lookup([1.0, 0.5, 3.0]);
+        assert!((out[0] - 1.0).abs() < 1e-5 && (out[1] - 0.5).abs() < 1e-5 && (out[2] - 2.0).abs() < 1e-5, "{out:?}");
+
+        let flat = text.replace("DOMAIN_MAX 2 2 2", "DOMAIN_MAX 2 0 2");
+        assert_eq!(ColorLut::parse_cube(&flat).unwrap_err(), "DOMAIN_MAX debe ser mayor que DOMAIN_MIN");
+    }
+}
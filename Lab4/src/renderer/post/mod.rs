@@ -0,0 +1,231 @@
+pub mod bloom;
+pub mod fxaa;
+pub mod film;
+pub mod lut;
+
+pub use bloom::Bloom;
+pub use fxaa::Fxaa;
+pub use film::{Vignette, Grain};
+pub use lut::ColorLut;
+
+use super::buffers::{ColorF, Framebuffer};
+use super::tonemap::ToneMapping;
+
+/// En qué punto del resolve corre un pase
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PostStage {
+    /// Radiancia lineal, antes del tone mapping (bloom)
+    Hdr,
+    /// Color de pantalla en [0,1], después del tone mapping
+    Display,
+}
+
+/// Imagen de un color por píxel sobre la que trabajan los pases
+pub struct PostImage {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<ColorF>,
+}
+
+impl PostImage {
+    /// Promedia las muestras del framebuffer sin tone mapping
+    pub fn from_framebuffer(fb: &Framebuffer) -> Self {
+        Self::average(fb, |_, c| c)
+    }
+
+    /// Tone mapping de cada muestra y después promedio, como `resolve` sin post. `glow`
+    /// (lineal, uno por píxel) se suma a todas las muestras del píxel antes de la curva.
+    pub fn tone_mapped(fb: &Framebuffer, tone: ToneMapping, glow: Option<&[ColorF]>) -> Self {
+        Self::average(fb, |i, c| match glow {
+            Some(g) => tone.map(ColorF::rgba(c.r + g[i].r, c.g + g[i].g, c.b + g[i].b, c.a)),
+            None => tone.map(c),
+        })
+    }
+
+    /// Promedio por píxel de `f(píxel, muestra)`
+    fn average(fb: &Framebuffer, f: impl Fn(usize, ColorF) -> ColorF) -> Self {
+        let n = fb.samples();
+        let inv = 1.0 / n as f32;
+        let avg = |i: usize, px: &mut dyn Iterator<Item = ColorF>| {
+            let mut acc = ColorF::rgba(0.0, 0.0, 0.0, 0.0);
+            for c in px.map(|c| f(i, c)) {
+                acc.r += c.r; acc.g += c.g; acc.b += c.b; acc.a += c.a;
+            }
+            ColorF::rgba(acc.r*inv, acc.g*inv, acc.b*inv, acc.a*inv)
+        };
+        let color = if fb.is_hdr() {
+            fb.color_hdr.chunks_exact(n).enumerate().map(|(i, px)| avg(i, &mut px.iter().copied())).collect()
+        } else {
+            fb.color.chunks_exact(n).enumerate().map(|(i, px)| avg(i, &mut px.iter().map(|&c| ColorF::from(c)))).collect()
+        };
+        Self { width: fb.width, height: fb.height, color }
+    }
+
+    #[inline]
+    pub fn get(&self, x: i32, y: i32) -> ColorF {
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        self.color[y*self.width + x]
+    }
+
+    /// Muestreo bilineal con coordenadas en píxeles (centro del píxel = +0.5), borde replicado
+    pub fn sample(&self, x: f32, y: f32) -> ColorF {
+        let (fx, fy) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let lerp = |a: ColorF, b: ColorF, t: f32| ColorF::rgba(
+            a.r + (b.r - a.r)*t, a.g + (b.g - a.g)*t, a.b + (b.b - a.b)*t, a.a + (b.a - a.a)*t,
+        );
+        let top = lerp(self.get(x0, y0), self.get(x0 + 1, y0), tx);
+        let bot = lerp(self.get(x0, y0 + 1), self.get(x0 + 1, y0 + 1), tx);
+        lerp(top, bot, ty)
+    }
+}
+
+/// Luminancia (Rec. 709)
+#[inline]
+pub fn luma(c: ColorF) -> f32 { 0.2126*c.r + 0.7152*c.g + 0.0722*c.b }
+
+/// Un efecto de post-proceso
+pub trait PostPass: Send {
+    /// Nombre corto, el mismo que acepta `PostChain::parse`
+    fn name(&self) -> &'static str;
+
+    fn stage(&self) -> PostStage { PostStage::Display }
+
+    /// `frame` sirve de semilla a los efectos animados (grano)
+    fn apply(&self, img: &mut PostImage, frame: u64);
+}
+
+struct Slot {
+    pass: Box<dyn PostPass>,
+    enabled: bool,
+}
+
+/// Cadena de pases, en orden. Vive en el `Framebuffer` y la aplica `resolve()`,
+/// así la pantalla y las capturas salen iguales.
+#[derive(Default)]
+pub struct PostChain {
+    passes: Vec<Slot>,
+    /// Semilla de los efectos animados; la actualiza la app cada frame
+    pub frame: u64,
+}
+
+impl PostChain {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn push(&mut self, pass: Box<dyn PostPass>) {
+        self.passes.push(Slot { pass, enabled: true });
+    }
+
+    /// Cadena desde una lista separada por comas: `bloom,fxaa,vignette,grain,lut=warm`.
+    /// `lut=` acepta un preset (`warm`, `cool`, `bleach`) o la ruta de un `.cube`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut chain = Self::new();
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, arg) = match item.split_once('=') {
+                Some((n, a)) => (n, Some(a)),
+                None => (item, None),
+            };
+            let pass: Box<dyn PostPass> = match name {
+                "bloom"    => Box::new(Bloom::default()),
+                "fxaa"     => Box::new(Fxaa::default()),
+                "vignette" => Box::new(Vignette::default()),
+                "grain"    => Box::new(Grain::default()),
+                "lut"      => Box::new(ColorLut::preset_or_file(arg.unwrap_or("warm"))?),
+                _ => return Err(format!("efecto desconocido: '{name}' (bloom, fxaa, vignette, grain, lut)")),
+            };
+            chain.push(pass);
+        }
+        Ok(chain)
+    }
+
+    /// Hay algún pase activo
+    pub fn is_active(&self) -> bool { self.passes.iter().any(|s| s.enabled) }
+
+    /// Hay algún pase activo en esa etapa
+    pub fn has_stage(&self, stage: PostStage) -> bool {
+        self.passes.iter().any(|s| s.enabled && s.pass.stage() == stage)
+    }
+
+    /// Activa o desactiva todos los pases con ese nombre; devuelve el nuevo estado
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let mut state = None;
+        for s in self.passes.iter_mut().filter(|s| s.pass.name() == name) {
+            s.enabled = !s.enabled;
+            state = Some(s.enabled);
+        }
+        state
+    }
+
+    /// Como `toggle`, pero si el pase no está en la cadena lo agrega al final con valores por defecto
+    pub fn toggle_or_add(&mut self, name: &str) -> Result<bool, String> {
+        if let Some(on) = self.toggle(name) { return Ok(on); }
+        self.passes.append(&mut Self::parse(name)?.passes);
+        Ok(true)
+    }
+
+    /// Nombres de los pases activos, en orden
+    pub fn enabled(&self) -> Vec<&'static str> {
+        self.passes.iter().filter(|s| s.enabled).map(|s| s.pass.name()).collect()
+    }
+
+    pub fn run(&self, stage: PostStage, img: &mut PostImage) {
+        for s in self.passes.iter().filter(|s| s.enabled && s.pass.stage() == stage) {
+            s.pass.apply(img, self.frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::buffers::{Color, ColorFormat, Msaa};
+
+    #[test]
+    fn chain_parses_and_toggles() {
+        let mut chain = PostChain::parse("bloom, fxaa,lut=cool").unwrap();
+        assert_eq!(chain.enabled(), ["bloom", "fxaa", "lut"]);
+        assert_eq!(chain.toggle("fxaa"), Some(false));
+        assert_eq!(chain.enabled(), ["bloom", "lut"]);
+        assert_eq!(chain.toggle("grain"), None);
+        assert_eq!(chain.toggle_or_add("grain"), Ok(true));
+        assert_eq!(chain.enabled(), ["bloom", "lut", "grain"]);
+        assert!(PostChain::parse("blur").is_err());
+    }
+
+    #[test]
+    fn resolve_applies_chain_to_screen_and_png_alike() {
+        let mut fb = Framebuffer::with_format(32, 16, Msaa::X4, ColorFormat::HdrF32);
+        fb.clear_color(Color::rgb(40, 40, 40));
+        fb.put_pixel(16, 8, 0.5, Color::rgb(255, 255, 255));
+        let plain = fb.resolve().to_vec();
+
+        fb.post = PostChain::parse("vignette,grain").unwrap();
+        fb.post.frame = 7;
+        let a = fb.resolve().to_vec();
+        let b = fb.resolve().to_vec();
+        assert!(a.iter().zip(&b).all(|(x, y)| x.to_rgba() == y.to_rgba()));
+        assert!(a.iter().zip(&plain).any(|(x, y)| x.to_rgba() != y.to_rgba()));
+    }
+
+    #[test]
+    fn post_keeps_the_msaa_edge_of_the_plain_resolve() {
+        // Píxel de borde: una muestra muy brillante y tres oscuras
+        let mut fb = Framebuffer::with_format(4, 4, Msaa::X4, ColorFormat::HdrF32);
+        fb.clear_color(Color::rgb(10, 10, 10));
+        fb.color_hdr[0] = ColorF::rgba(20.0, 20.0, 20.0, 1.0);
+        let plain = fb.resolve().to_vec();
+
+        fb.post.push(Box::new(Vignette { strength: 0.0, inner: 0.35 }));
+        let post = fb.resolve().to_vec();
+        assert_eq!(post[0].to_rgba(), plain[0].to_rgba());
+        assert!(post.iter().zip(&plain).all(|(x, y)| x.to_rgba() == y.to_rgba()));
+
+        // Un bloom sin umbral alcanzable no agrega nada y tampoco cambia el orden
+        fb.post.push(Box::new(Bloom { threshold: 1e9, ..Bloom::default() }));
+        let bloom = fb.resolve().to_vec();
+        assert!(bloom.iter().zip(&plain).all(|(x, y)| x.to_rgba() == y.to_rgba()));
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use crate::math::{Mat4, Vec4};
+use crate::scene::Mesh;
+use super::blend::BlendState;
+use super::buffers::Framebuffer;
+use super::pipeline::{Shader, PipelineState, CullMode, draw_mesh};
+use super::raster::DrawStats;
+use super::shadow::{ShadowMap, ShadowSettings};
+use super::uniforms::Uniforms;
+
+/// Alpha mínimo para que un draw transparente proyecte sombra
+const SHADOW_ALPHA_CUTOFF: f32 = 0.3;
+
+/// Un draw call diferido
+pub struct DrawCall<'a> {
+    pub mesh: &'a Mesh,
+    pub shader: Box<dyn Shader + 'a>,
+    pub uniforms: Uniforms,
+    pub state: PipelineState,
+}
+
+impl DrawCall<'_> {
+    /// z en view space del origen del modelo (más negativo = más lejos)
+    fn view_depth(&self) -> f32 {
+        (self.uniforms.view * self.uniforms.model * Vec4::new(0.0, 0.0, 0.0, 1.0)).z
+    }
+}
+
+/// Cola de draws de un frame: opcionalmente un pase de sombras, luego los opacos
+/// en orden de envío y por último los transparentes de atrás hacia adelante.
+#[derive(Default)]
+pub struct RenderQueue<'a> {
+    opaque: Vec<DrawCall<'a>>,
+    transparent: Vec<DrawCall<'a>>,
+    shadows: Option<(ShadowSettings, &'a mut Option<Arc<ShadowMap>>)>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn submit(&mut self, mesh: &'a Mesh, shader: Box<dyn Shader + 'a>, uniforms: &Uniforms, state: &PipelineState) {
+        let call = DrawCall { mesh, shader, uniforms: uniforms.clone(), state: *state };
+        if state.is_transparent() {
+            self.transparent.push(call);
+        } else {
+            self.opaque.push(call);
+        }
+    }
+
+    /// Renderiza un shadow map antes de los pases de color y lo pasa a todos los draws.
+    /// El mapa queda en `map`, que conviene conservar entre frames: mientras nadie más
+    /// lo retenga, el frame siguiente reusa su memoria.
+    pub fn enable_shadows(&mut self, settings: ShadowSettings, map: &'a mut Option<Arc<ShadowMap>>) {
+        self.shadows = Some((settings, map));
+    }
+
+    /// Pase solo de profundidad desde la luz con los draws que proyectan sombra, sobre
+    /// `map` ya ajustado y vacío. Usa el vertex shader de cada draw, así las órbitas
+    /// animadas coinciden.
+    fn render_shadow_map(&mut self, map: &mut ShadowMap) {
+        let mut fb = Framebuffer::depth_only(map.size, map.size, std::mem::take(&mut map.depth));
+        let vp = map.viewport();
+
+        for c in self.opaque.iter_mut().chain(self.transparent.iter_mut()) {
+            if !c.state.cast_shadow { continue; }
+            let transparent = c.state.is_transparent();
+            let state = PipelineState {
+                cull: if transparent { CullMode::None } else { c.state.cull },
+                depth_write: true,
+                color_write: false,
+                // Los transparentes proyectan sombra donde son suficientemente opacos
+                alpha_test: if transparent { Some(SHADOW_ALPHA_CUTOFF) } else { c.state.alpha_test },
+                blend: BlendState::OPAQUE,
+                ..c.state
+            };
+            let u = map.light_uniforms(&c.uniforms);
+            draw_mesh(&mut fb, c.mesh, &mut *c.shader, &u, vp, &state);
+        }
+        map.depth = fb.depth;
+    }
+
+    /// Dibuja y vacía la cola
+    pub fn flush(&mut self, fb: &mut Framebuffer, viewport: Mat4) -> DrawStats {
+        let mut stats = DrawStats::default();
+
+        if let Some((settings, slot)) = self.shadows.take() {
+            let map = match slot {
+                Some(map) => {
+                    Arc::make_mut(map).update(&settings);
+                    map
+                }
+                None => slot.insert(Arc::new(ShadowMap::new(&settings))),
+            };
+            self.render_shadow_map(Arc::make_mut(map));
+            for c in self.opaque.iter_mut().chain(self.transparent.iter_mut()) {
+                c.uniforms.shadow = Some(map.clone());
+            }
+        }
+
+        for mut c in self.opaque.drain(..) {
+            stats += draw_mesh(fb, c.mesh, &mut *c.shader, &c.uniforms, viewport, &c.state);
+        }
+
+        // Orden estable: a igual profundidad se respeta el orden de envío
+        self.transparent.sort_by(|a, b| a.view_depth().total_cmp(&b.view_depth()));
+        for mut c in self.transparent.drain(..) {
+            stats += draw_mesh(fb, c.mesh, &mut *c.shader, &c.uniforms, viewport, &c.state);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::math::mat::{scale, translate};
+    use crate::shaders::{common::shadow, make_shader, ShaderKind};
+    use crate::utils::testing::sphere;
+
+    #[test]
+    fn occluder_between_light_and_planet_casts_shadow() {
+        let mesh = sphere();
+        let light = Vec3::new(0.5, 0.7, 0.2).normalize();
+        let state = PipelineState { cull: CullMode::Back, ..Default::default() };
+
+        // Planeta en el origen, "luna" entre él y la luz
+        let planet = Uniforms { light_dir: light, ..Default::default() };
+        let moon_center = light * 1.4;
+        let moon = Uniforms { model: translate(moon_center) * scale(Vec3::new(0.4, 0.4, 0.4)), ..planet.clone() };
+
+        let mut queue = RenderQueue::new();
+        queue.submit(&mesh, make_shader(ShaderKind::Flat), &planet, &state);
+        queue.submit(&mesh, make_shader(ShaderKind::Flat), &moon, &state);
+        let settings = ShadowSettings { size: 256, light_dir: light, radius: 2.5, ..Default::default() };
+        let mut map = ShadowMap::new(&settings);
+        queue.render_shadow_map(&mut map);
+        let u = Uniforms { shadow: Some(Arc::new(map)), ..planet };
+
+        // Punto del planeta que mira a la luz: eclipsado; cara de la luna hacia la luz: iluminada
+        let r = 0.5;
+        assert!(shadow(&u, light * r, light) < 0.05);
+        assert!(shadow(&u, moon_center + light * 0.2, light) > 0.95);
+        // Un punto del planeta al costado, fuera de la sombra de la luna
+        let side = light.cross(Vec3::new(0.0, 1.0, 0.0)).normalize();
+        let p = (side + light * 0.6).normalize() * r;
+        assert!(shadow(&u, p, p.normalize()) > 0.95);
+    }
+
+    #[test]
+    fn shadow_map_is_reused_between_frames() {
+        let mesh = sphere();
+        let u = Uniforms::default();
+        let settings = ShadowSettings { size: 64, light_dir: u.light_dir, radius: 2.0, ..Default::default() };
+        let mut fb = Framebuffer::new(32, 32);
+        let vp = crate::math::viewport(0.0, 0.0, 32.0, 32.0, 1.0);
+
+        let mut slot = None;
+        let mut frame = |with_planet: bool, slot: &mut Option<Arc<ShadowMap>>| {
+            let mut queue = RenderQueue::new();
+            if with_planet {
+                queue.submit(&mesh, make_shader(ShaderKind::Flat), &u, &PipelineState::default());
+            }
+            queue.enable_shadows(settings, slot);
+            queue.flush(&mut fb, vp);
+        };
+        frame(true, &mut slot);
+        let first = slot.as_ref().unwrap();
+        assert!(first.depth_at(32, 32) < f32::INFINITY);
+        let ptr = first.depth.as_ptr();
+
+        // Mismo buffer, limpio: sin draws no queda nada del frame anterior
+        frame(false, &mut slot);
+        let map = slot.as_ref().unwrap();
+        assert_eq!(Arc::strong_count(map), 1);
+        assert_eq!(map.depth.as_ptr(), ptr);
+        assert!(map.depth.iter().all(|&z| z == f32::INFINITY));
+    }
+}
@@ -1,42 +1,95 @@
 use crate::math::{Vec2, Vec3, Vec4};
 
-use super::buffers::{Framebuffer, Color};
+use super::buffers::{Framebuffer, Color, ColorF, ColorFormat, Msaa};
+use super::blend::BlendState;
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Varyings {
     pub pos_ws: Vec3,
     pub nrm_ws: Vec3,
     pub uv: Vec2,
+    /// Posición en espacio objeto (para patrones que giran con el modelo)
+    pub pos_os: Vec3,
 }
 
+/// Operaciones por fragmento (profundidad + mezcla) para un triángulo
 #[derive(Copy, Clone, Debug)]
-pub struct RasterInput {
+pub struct FragmentOps {
+    /// Test de z antes de llamar al fragment shader
+    pub early_z: bool,
+    pub depth_write: bool,
+    /// `false` = pase solo de profundidad (no se escribe color)
+    pub color_write: bool,
+    /// Descarta fragmentos con alpha menor (se evalúa después del fragment)
+    pub alpha_cutoff: Option<f32>,
+    pub blend: BlendState,
+}
+
+/// Contadores por draw call
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DrawStats {
+    pub triangles: u32,
+    pub fragments_shaded: u64,
+    /// Invocaciones de fragment evitadas por early-z
+    pub fragments_early_rejected: u64,
+}
+
+impl core::ops::AddAssign for DrawStats {
+    fn add_assign(&mut self, o: Self) {
+        self.triangles += o.triangles;
+        self.fragments_shaded += o.fragments_shaded;
+        self.fragments_early_rejected += o.fragments_early_rejected;
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct RasterInput {
     pub p: [Vec4; 3],       
     pub z: [f32; 3],        
     pub inv_w: [f32; 3],    
     pub v: [Varyings; 3],   
 }
 
-#[inline(always)]
-fn barycentric(p0: (f32,f32), p1: (f32,f32), p2: (f32,f32), px: (f32,f32)) -> (f32,f32,f32,f32) {
-    let (x0,y0) = p0; let (x1,y1) = p1; let (x2,y2) = p2; let (x,y) = px;
-    let denom = (y1 - y2)*(x0 - x2) + (x2 - x1)*(y0 - y2);
-    let w0 = ((y1 - y2)*(x - x2) + (x2 - x1)*(y - y2)) / denom;
-    let w1 = ((y2 - y0)*(x - x2) + (x0 - x2)*(y - y2)) / denom;
-    let w2 = 1.0 - w0 - w1;
-    (w0,w1,w2,denom)
-}
+/// Bits de subpíxel de las coordenadas en punto fijo (1/256 px)
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL_ONE: i64 = 1 << SUBPIXEL_BITS;
 
 #[inline(always)]
-fn inside_triangle(w0: f32, w1: f32, w2: f32) -> bool {
-    w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0
+fn to_fixed(v: f32) -> i64 { (v * SUBPIXEL_ONE as f32).round() as i64 }
+
+/// Función de arista a->b evaluada en p (punto fijo). Positiva "dentro" con área > 0.
+#[derive(Copy, Clone, Debug)]
+struct Edge {
+    dx: i64,
+    dy: i64,
+    /// Incremento al avanzar un píxel en x / en y
+    step_x: i64,
+    step_y: i64,
+    /// Valor en la primera muestra del rectángulo
+    origin: i64,
+    /// 0 en aristas top/left, -1 en el resto: una muestra exactamente sobre la
+    /// arista compartida solo la toma uno de los dos triángulos
+    bias: i64,
 }
 
-/// Interpolación en perspectiva de un atributo escalar
-#[inline(always)]
-fn persp_interp1(w: [f32;3], a: [f32;3]) -> f32 {
-    let denom = w[0] + w[1] + w[2];
-    (a[0]*w[0] + a[1]*w[1] + a[2]*w[2]) / denom
+impl Edge {
+    fn new(a: (i64, i64), b: (i64, i64), start: (i64, i64)) -> Self {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        // y hacia abajo: "left" = interior a la derecha (dy < 0),
+        // "top" = horizontal con el interior debajo (dx > 0)
+        let top_left = dy < 0 || (dy == 0 && dx > 0);
+        Edge {
+            dx, dy,
+            step_x: -dy * SUBPIXEL_ONE,
+            step_y: dx * SUBPIXEL_ONE,
+            origin: dx * (start.1 - a.1) - dy * (start.0 - a.0),
+            bias: if top_left { 0 } else { -1 },
+        }
+    }
+
+    /// Cambio del valor al desplazar la muestra `d` (punto fijo)
+    #[inline(always)]
+    fn delta(&self, d: (i64, i64)) -> i64 { -self.dy * d.0 + self.dx * d.1 }
 }
 
 /// Interpolación en perspectiva de Vec2
@@ -58,50 +111,280 @@ fn persp_interp3(w: [f32;3], a: [Vec3;3]) -> Vec3 {
     Vec3::new(ax/denom, ay/denom, az/denom)
 }
 
-/// Rasteriza un triángulo usando barycentrics + zbuffer y llama a `shade_pixel(x,y, vary, z)`.
-pub fn raster_triangle<F: FnMut(i32, i32, f32, Varyings) -> Color>(
-    fb: &mut Framebuffer,
+/// Rectángulo de píxeles (inclusivo)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PixelRect {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl PixelRect {
+    pub fn intersect(self, o: PixelRect) -> Option<PixelRect> {
+        let r = PixelRect {
+            min_x: self.min_x.max(o.min_x),
+            min_y: self.min_y.max(o.min_y),
+            max_x: self.max_x.min(o.max_x),
+            max_y: self.max_y.min(o.max_y),
+        };
+        if r.min_x > r.max_x || r.min_y > r.max_y { None } else { Some(r) }
+    }
+}
+
+/// Bounding box en pantalla del triángulo, recortado a `width x height`
+pub(crate) fn triangle_bounds(tri: &RasterInput, width: usize, height: usize) -> Option<PixelRect> {
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let min_x = tri.p.iter().map(|p| p.x).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
+    let max_x = tri.p.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max).ceil().min((width - 1) as f32) as i32;
+    let min_y = tri.p.iter().map(|p| p.y).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
+    let max_y = tri.p.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max).ceil().min((height - 1) as f32) as i32;
+
+    if min_x > max_x || min_y > max_y { None } else { Some(PixelRect { min_x, min_y, max_x, max_y }) }
+}
+
+/// Muestras de color del destino, según el formato del framebuffer
+pub(crate) enum ColorSamples<'a> {
+    Ldr(&'a mut [Color]),
+    Hdr(&'a mut [ColorF]),
+    /// Destino solo de profundidad
+    None,
+}
+
+/// Vista mutable sobre un bloque de filas completas del framebuffer (`y0..y0+rows`),
+/// con todas sus muestras. Permite repartir el framebuffer entre hilos sin solapamiento.
+pub(crate) struct RasterTarget<'a> {
+    pub width: usize,
+    pub y0: usize,
+    pub msaa: Msaa,
+    pub color: ColorSamples<'a>,
+    pub depth: &'a mut [f32],
+}
+
+impl<'a> RasterTarget<'a> {
+    pub fn full(fb: &'a mut Framebuffer) -> Self {
+        let rows = fb.height.max(1);
+        Self::split_rows(fb, rows).pop().expect("framebuffer vacío")
+    }
+
+    /// Parte el framebuffer en bloques contiguos de `rows` filas
+    pub fn split_rows(fb: &'a mut Framebuffer, rows: usize) -> Vec<RasterTarget<'a>> {
+        let (width, msaa) = (fb.width, fb.msaa);
+        let len = (rows * width * msaa.samples()).max(1);
+        let colors: Vec<ColorSamples<'a>> = match fb.format {
+            ColorFormat::Ldr8   => fb.color.chunks_mut(len).map(ColorSamples::Ldr).collect(),
+            ColorFormat::HdrF32 => fb.color_hdr.chunks_mut(len).map(ColorSamples::Hdr).collect(),
+            ColorFormat::Depth  => fb.depth.chunks(len).map(|_| ColorSamples::None).collect(),
+        };
+        colors.into_iter()
+            .zip(fb.depth.chunks_mut(len))
+            .enumerate()
+            .map(|(i, (color, depth))| RasterTarget { width, y0: i * rows, msaa, color, depth })
+            .collect()
+    }
+
+    /// Escribe (con mezcla) las muestras `s` de `[base, base+8)` marcadas en `pass`.
+    /// Con `color_write == false` solo toca la profundidad.
+    #[inline(always)]
+    fn write_samples(&mut self, base: usize, pass: u32, zs: &[f32; 8], col: ColorF, ops: FragmentOps) {
+        let mut bits = pass;
+        if ops.depth_write {
+            while bits != 0 {
+                let s = bits.trailing_zeros() as usize;
+                self.depth[base + s] = zs[s];
+                bits &= bits - 1;
+            }
+        }
+        if !ops.color_write { return; }
+        let mut bits = pass;
+        match &mut self.color {
+            ColorSamples::Ldr(color) => {
+                let c = col.to_color();
+                while bits != 0 {
+                    let i = base + bits.trailing_zeros() as usize;
+                    color[i] = ops.blend.apply(c, color[i]);
+                    bits &= bits - 1;
+                }
+            }
+            ColorSamples::Hdr(color) => {
+                while bits != 0 {
+                    let i = base + bits.trailing_zeros() as usize;
+                    color[i] = ops.blend.apply_f(col, color[i]);
+                    bits &= bits - 1;
+                }
+            }
+            ColorSamples::None => {}
+        }
+    }
+}
+
+/// Rasteriza un triángulo con funciones de arista incrementales en punto fijo
+/// (regla top-left) + zbuffer y llama a `shade_pixel(x,y, vary, z)`.
+/// Solo toca los píxeles dentro de `rect`, que debe caer en las filas de `target`.
+/// Con `ops.early_z` el test de z se hace antes de sombrear.
+pub(crate) fn raster_triangle<F: FnMut(i32, i32, f32, Varyings) -> ColorF>(
+    target: &mut RasterTarget,
+    rect: PixelRect,
     tri: &RasterInput,
+    ops: FragmentOps,
+    stats: &mut DrawStats,
     mut shade_pixel: F,
 ) {
-    if fb.width == 0 || fb.height == 0 {
-        return;
+    let mut fx = [
+        (to_fixed(tri.p[0].x), to_fixed(tri.p[0].y)),
+        (to_fixed(tri.p[1].x), to_fixed(tri.p[1].y)),
+        (to_fixed(tri.p[2].x), to_fixed(tri.p[2].y)),
+    ];
+    let mut order = [0usize, 1, 2];
+    let mut area = (fx[1].0 - fx[0].0)*(fx[2].1 - fx[0].1) - (fx[1].1 - fx[0].1)*(fx[2].0 - fx[0].0);
+    if area == 0 { return; }
+    // Orientación única: con área negativa se intercambian v1 y v2
+    if area < 0 {
+        fx.swap(1, 2);
+        order.swap(1, 2);
+        area = -area;
     }
+    let z_in = [tri.z[order[0]], tri.z[order[1]], tri.z[order[2]]];
+    let inv_w = [tri.inv_w[order[0]], tri.inv_w[order[1]], tri.inv_w[order[2]]];
+    let v = [tri.v[order[0]], tri.v[order[1]], tri.v[order[2]]];
 
-    // Bounding box 
-    let fb_width = fb.width as i32;
-    let fb_height = fb.height as i32;
-    
-    let min_x = tri.p.iter().map(|p| p.x).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
-    let max_x = tri.p.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max).ceil().min((fb_width - 1) as f32) as i32;
-    let min_y = tri.p.iter().map(|p| p.y).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
-    let max_y = tri.p.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max).ceil().min((fb_height - 1) as f32) as i32;
+    // Primera muestra: centro del píxel (min_x, min_y)
+    let start = (
+        ((rect.min_x as i64) << SUBPIXEL_BITS) + SUBPIXEL_ONE / 2,
+        ((rect.min_y as i64) << SUBPIXEL_BITS) + SUBPIXEL_ONE / 2,
+    );
+    // e0 es opuesta al vértice 0 (peso de v0), etc.
+    let e = [
+        Edge::new(fx[1], fx[2], start),
+        Edge::new(fx[2], fx[0], start),
+        Edge::new(fx[0], fx[1], start),
+    ];
+    let inv_area = 1.0 / area as f32;
 
-    if min_x > max_x || min_y > max_y {
-        return;
+    // Aporte de cada muestra MSAA a cada arista, relativo al centro del píxel
+    let offsets = target.msaa.sample_offsets();
+    let n = offsets.len();
+    let mut sample_e = [[0i64; 3]; 8];
+    for (s, &(ox, oy)) in offsets.iter().enumerate() {
+        let d = (ox as i64 * SUBPIXEL_ONE / 16, oy as i64 * SUBPIXEL_ONE / 16);
+        sample_e[s] = [e[0].delta(d), e[1].delta(d), e[2].delta(d)];
     }
+    let covers = |ev: [i64; 3], s: usize| {
+        (ev[0] + sample_e[s][0] + e[0].bias)
+            | (ev[1] + sample_e[s][1] + e[1].bias)
+            | (ev[2] + sample_e[s][2] + e[2].bias) >= 0
+    };
+    let weights = |ev: [i64; 3], s: usize| {
+        let [a, b, c] = sample_e[s];
+        ((ev[0] + a) as f32 * inv_area, (ev[1] + b) as f32 * inv_area, (ev[2] + c) as f32 * inv_area)
+    };
+
+    let mut row_e = [e[0].origin, e[1].origin, e[2].origin];
+    for y in rect.min_y..=rect.max_y {
+        let row = (y as usize - target.y0) * target.width;
+        let mut ev = row_e;
+        for x in rect.min_x..=rect.max_x {
+            let here = ev;
+            ev[0] += e[0].step_x;
+            ev[1] += e[1].step_x;
+            ev[2] += e[2].step_x;
+
+            // Cobertura por muestra
+            let mut mask = 0u32;
+            for s in 0..n {
+                if covers(here, s) { mask |= 1 << s; }
+            }
+            if mask == 0 { continue; }
+
+            // Profundidad por muestra
+            let base = (row + x as usize) * n;
+            let mut zs = [0.0f32; 8];
+            let mut pass = 0u32;
+            for (s, zs) in zs.iter_mut().enumerate().take(n) {
+                if mask & (1 << s) == 0 { continue; }
+                let (w0, w1, w2) = weights(here, s);
+                *zs = w0*z_in[0] + w1*z_in[1] + w2*z_in[2];
+                if *zs < target.depth[base + s] { pass |= 1 << s; }
+            }
+            if ops.early_z && pass == 0 {
+                stats.fragments_early_rejected += 1;
+                continue;
+            }
+            // Solo profundidad y sin alpha test: no hace falta el fragment shader
+            if !ops.color_write && ops.alpha_cutoff.is_none() {
+                target.write_samples(base, pass, &zs, ColorF::default(), ops);
+                continue;
+            }
 
-    let p0 = (tri.p[0].x, tri.p[0].y);
-    let p1 = (tri.p[1].x, tri.p[1].y);
-    let p2 = (tri.p[2].x, tri.p[2].y);
-
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let (w0,w1,w2,_) = barycentric(p0,p1,p2, (x as f32 + 0.5, y as f32 + 0.5));
-            if !inside_triangle(w0,w1,w2) { continue; }
-
-            let w0p = w0 * tri.inv_w[0];
-            let w1p = w1 * tri.inv_w[1];
-            let w2p = w2 * tri.inv_w[2];
-            let z = w0*tri.z[0] + w1*tri.z[1] + w2*tri.z[2];
-            let pos_ws = persp_interp3([w0p,w1p,w2p], [tri.v[0].pos_ws, tri.v[1].pos_ws, tri.v[2].pos_ws]);
-            let mut nrm_ws = persp_interp3([w0p,w1p,w2p], [tri.v[0].nrm_ws, tri.v[1].nrm_ws, tri.v[2].nrm_ws]).normalize();
-            if !nrm_ws.length().is_finite() { nrm_ws = tri.v[0].nrm_ws; }
+            // Un fragmento por píxel: en el centro, o en la primera muestra cubierta
+            // si el centro cae fuera (evita extrapolar varyings en los bordes)
+            let center = e.iter().zip(here).all(|(ed, v)| v + ed.bias >= 0);
+            let (w0, w1, w2) = if center {
+                (here[0] as f32 * inv_area, here[1] as f32 * inv_area, here[2] as f32 * inv_area)
+            } else {
+                weights(here, mask.trailing_zeros() as usize)
+            };
+            let z = w0*z_in[0] + w1*z_in[1] + w2*z_in[2];
+
+            let w0p = w0 * inv_w[0];
+            let w1p = w1 * inv_w[1];
+            let w2p = w2 * inv_w[2];
+            let pos_ws = persp_interp3([w0p,w1p,w2p], [v[0].pos_ws, v[1].pos_ws, v[2].pos_ws]);
+            let mut nrm_ws = persp_interp3([w0p,w1p,w2p], [v[0].nrm_ws, v[1].nrm_ws, v[2].nrm_ws]).normalize();
+            if !nrm_ws.length().is_finite() { nrm_ws = v[0].nrm_ws; }
             
-            let uv = persp_interp2([w0p,w1p,w2p], [tri.v[0].uv, tri.v[1].uv, tri.v[2].uv]);
-            let vary = Varyings { pos_ws, nrm_ws, uv };
+            let uv = persp_interp2([w0p,w1p,w2p], [v[0].uv, v[1].uv, v[2].uv]);
+            let pos_os = persp_interp3([w0p,w1p,w2p], [v[0].pos_os, v[1].pos_os, v[2].pos_os]);
+            let vary = Varyings { pos_ws, nrm_ws, uv, pos_os };
             let col = shade_pixel(x, y, z, vary);
-            fb.put_pixel(x, y, z, col);
+            stats.fragments_shaded += 1;
+            if ops.alpha_cutoff.is_some_and(|c| col.a < c) { continue; }
+            target.write_samples(base, pass, &zs, col, ops);
+        }
+        row_e[0] += e[0].step_y;
+        row_e[1] += e[1].step_y;
+        row_e[2] += e[2].step_y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tri(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> RasterInput {
+        let p = |q: (f32, f32)| Vec4::new(q.0, q.1, 0.5, 1.0);
+        RasterInput { p: [p(a), p(b), p(c)], z: [0.5; 3], inv_w: [1.0; 3], v: [Varyings::default(); 3] }
+    }
+
+    #[test]
+    fn shared_edges_cover_each_pixel_once() {
+        // Abanico de triángulos (ambos windings) alrededor de un centro fraccionario
+        let (w, h) = (32usize, 32usize);
+        let mut fb = Framebuffer::new(w, h);
+        let c = (15.3, 16.7);
+        let ring = [(2.0, 2.0), (16.5, 1.0), (30.0, 2.0), (31.0, 16.25), (30.0, 30.0), (16.0, 31.0), (2.0, 30.0), (1.0, 15.5)];
+        let ops = FragmentOps { early_z: false, depth_write: false, color_write: true, alpha_cutoff: None, blend: BlendState::OPAQUE };
+        let mut hits = vec![0u32; w * h];
+        let mut stats = DrawStats::default();
+        for k in 0..ring.len() {
+            let (a, b) = (ring[k], ring[(k + 1) % ring.len()]);
+            let t = if k % 2 == 0 { tri(c, a, b) } else { tri(c, b, a) };
+            let Some(rect) = triangle_bounds(&t, w, h) else { continue };
+            let mut target = RasterTarget::full(&mut fb);
+            raster_triangle(&mut target, rect, &t, ops, &mut stats, |x, y, _, _| {
+                hits[y as usize * w + x as usize] += 1;
+                ColorF::rgb(1.0, 1.0, 1.0)
+            });
+        }
+        assert!(hits.iter().all(|&n| n <= 1), "píxeles dibujados dos veces");
+        // Todo el interior del octágono queda cubierto
+        for y in 4..28 {
+            for x in 4..28 {
+                assert_eq!(hits[y * w + x], 1, "hueco en ({x},{y})");
+            }
         }
     }
-}
\ No newline at end of file
+}
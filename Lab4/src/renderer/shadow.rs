@@ -0,0 +1,138 @@
+use crate::math::{Vec3, Vec4, Mat4};
+use crate::math::mat::{look_at_rh, ortho_rh, viewport};
+use super::uniforms::Uniforms;
+
+/// Cómo ajustar el shadow map de la luz direccional a la escena
+#[derive(Copy, Clone, Debug)]
+pub struct ShadowSettings {
+    /// Resolución (cuadrada) del mapa
+    pub size: usize,
+    /// Hacia dónde está la luz (misma convención que `Uniforms::light_dir`)
+    pub light_dir: Vec3,
+    /// Esfera que debe quedar dentro del mapa
+    pub center: Vec3,
+    pub radius: f32,
+    /// Sesgo de profundidad, en unidades de mundo
+    pub bias: f32,
+    /// Desplazamiento a lo largo de la normal al muestrear, en texels
+    pub normal_offset: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            light_dir: Uniforms::default().light_dir,
+            center: Vec3::new(0.0, 0.0, 0.0),
+            radius: 4.0,
+            bias: 0.02,
+            normal_offset: 1.5,
+        }
+    }
+}
+
+/// Profundidad vista desde la luz (proyección ortográfica). `depth[y*size + x]`
+/// usa la misma convención que el z-buffer: menor = más cerca de la luz.
+#[derive(Clone, Debug)]
+pub struct ShadowMap {
+    pub size: usize,
+    pub view: Mat4,
+    pub proj: Mat4,
+    pub depth: Vec<f32>,
+    /// Mundo -> (x, y en texels; z en profundidad del mapa)
+    to_map: Mat4,
+    /// Sesgo ya convertido a unidades de profundidad
+    depth_bias: f32,
+    /// Desplazamiento por normal en unidades de mundo
+    normal_offset: f32,
+}
+
+impl ShadowMap {
+    /// Mapa vacío (todo iluminado) ajustado a `s`
+    pub fn new(s: &ShadowSettings) -> Self {
+        let mut map = Self {
+            size: 0, view: Mat4::identity(), proj: Mat4::identity(), depth: Vec::new(),
+            to_map: Mat4::identity(), depth_bias: 0.0, normal_offset: 0.0,
+        };
+        map.update(s);
+        map
+    }
+
+    /// Reajusta el mapa a `s` y lo vacía, reusando la memoria de `depth`
+    pub fn update(&mut self, s: &ShadowSettings) {
+        let size = s.size.max(1);
+        let r = s.radius.max(1e-3);
+        let dir = s.light_dir.normalize();
+        let eye = s.center + dir * (2.0 * r);
+        // `up` no puede ser paralelo a la dirección de la luz
+        let up = if dir.y.abs() > 0.99 { Vec3::new(0.0, 0.0, 1.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+        let view = look_at_rh(eye, s.center, up);
+        let (near, far) = (r, 3.0 * r);
+        let proj = ortho_rh(-r, r, -r, r, near, far);
+        self.size = size;
+        self.view = view;
+        self.proj = proj;
+        self.to_map = viewport(0.0, 0.0, size as f32, size as f32, 1.0) * proj * view;
+        self.depth.clear();
+        self.depth.resize(size*size, f32::INFINITY);
+        // El viewport lleva [near, far] a [0, 1]
+        self.depth_bias = s.bias / (far - near);
+        self.normal_offset = s.normal_offset * 2.0 * r / size as f32;
+    }
+
+    /// Viewport con el que se rasteriza el pase de sombras
+    pub fn viewport(&self) -> Mat4 {
+        viewport(0.0, 0.0, self.size as f32, self.size as f32, 1.0)
+    }
+
+    /// Uniforms del pase de sombras: los del draw call, vistos desde la luz
+    pub fn light_uniforms(&self, u: &Uniforms) -> Uniforms {
+        let inv_dir = self.view.as_array()[2];
+        Uniforms {
+            view: self.view,
+            proj: self.proj,
+            // Solo para shaders que miren la cámara: un punto lejano en dirección a la luz
+            camera_pos: Vec3::new(inv_dir[0], inv_dir[1], inv_dir[2]) * 1.0e4,
+            shadow: None,
+            ..u.clone()
+        }
+    }
+
+    /// Posición en el mapa (texels) y profundidad sesgada de un punto del mundo,
+    /// desplazado `normal_offset` a lo largo de `nrm`
+    #[inline]
+    pub fn project(&self, pos_ws: Vec3, nrm_ws: Vec3) -> (f32, f32, f32) {
+        let p = pos_ws + nrm_ws * self.normal_offset;
+        let m = self.to_map * Vec4::from3(p, 1.0);
+        (m.x, m.y, m.z - self.depth_bias)
+    }
+
+    /// Profundidad del texel; fuera del mapa no hay oclusores
+    #[inline]
+    pub fn depth_at(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 || x >= self.size as i32 || y >= self.size as i32 {
+            return f32::INFINITY;
+        }
+        self.depth[y as usize * self.size + x as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_scene_sphere() {
+        let s = ShadowSettings { size: 64, light_dir: Vec3::new(0.3, 1.0, 0.2), radius: 2.0, bias: 0.0, normal_offset: 0.0, ..Default::default() };
+        let map = ShadowMap::new(&s);
+        let n = Vec3::new(0.0, 0.0, 0.0);
+
+        let (x, y, z) = map.project(s.center, n);
+        assert!((x - 32.0).abs() < 1e-3 && (y - 32.0).abs() < 1e-3);
+        assert!((z - 0.5).abs() < 1e-4);
+
+        // Más cerca de la luz = menor profundidad
+        let (_, _, z_near) = map.project(s.light_dir.normalize() * 1.5, n);
+        assert!(z_near < z && z_near > 0.0);
+    }
+}
@@ -0,0 +1,149 @@
+use std::sync::Mutex;
+
+use super::buffers::{Framebuffer, ColorF};
+use super::raster::{RasterInput, RasterTarget, PixelRect, Varyings, FragmentOps, DrawStats, raster_triangle};
+
+/// Cómo rasterizar los triángulos de un draw call
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RasterMode {
+    /// Un hilo, triángulo por triángulo sobre todo el framebuffer
+    Single,
+    /// Triángulos agrupados en tiles de `tile_size` px; las filas de tiles se
+    /// sombrean en paralelo. `threads == 0` usa todos los núcleos.
+    Tiled { tile_size: usize, threads: usize },
+}
+
+impl Default for RasterMode {
+    fn default() -> Self { RasterMode::Tiled { tile_size: 64, threads: 0 } }
+}
+
+/// Número de hilos efectivo (`0` = auto)
+pub(crate) fn worker_count(threads: usize) -> usize {
+    if threads > 0 { return threads; }
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Rasteriza triángulos ya en pantalla (con su bounding box) por tiles.
+///
+/// Cada píxel pertenece a un único tile y dentro de un tile los triángulos se
+/// procesan en el orden de envío, así que el resultado es idéntico bit a bit
+/// al del camino de un solo hilo.
+pub(crate) fn raster_tiled<F>(
+    fb: &mut Framebuffer,
+    tris: &[(RasterInput, PixelRect)],
+    ops: FragmentOps,
+    tile_size: usize,
+    threads: usize,
+    stats: &mut DrawStats,
+    shade_pixel: &F,
+) where
+    F: Fn(i32, i32, f32, Varyings) -> ColorF + Sync,
+{
+    let (width, height) = (fb.width, fb.height);
+    if width == 0 || height == 0 || tris.is_empty() { return; }
+
+    let tile = tile_size.max(8);
+    let tiles_x = width.div_ceil(tile);
+    let tiles_y = height.div_ceil(tile);
+
+    // Binning: índices de triángulo por tile, en orden de envío
+    let mut bins: Vec<Vec<u32>> = vec![Vec::new(); tiles_x * tiles_y];
+    for (i, (_, b)) in tris.iter().enumerate() {
+        for ty in (b.min_y as usize / tile)..=(b.max_y as usize / tile) {
+            for tx in (b.min_x as usize / tile)..=(b.max_x as usize / tile) {
+                bins[ty * tiles_x + tx].push(i as u32);
+            }
+        }
+    }
+
+    // Una fila de tiles = bloque contiguo de color/depth; se reparten dinámicamente
+    let work = Mutex::new(RasterTarget::split_rows(fb, tile).into_iter().enumerate());
+    let bins = &bins;
+
+    let run = || {
+        let mut local = DrawStats::default();
+        loop {
+            let next = work.lock().unwrap().next();
+            let Some((ty, mut target)) = next else { break };
+
+            for tx in 0..tiles_x {
+                let rect = PixelRect {
+                    min_x: (tx * tile) as i32,
+                    min_y: (ty * tile) as i32,
+                    max_x: (((tx + 1) * tile).min(width) - 1) as i32,
+                    max_y: (((ty + 1) * tile).min(height) - 1) as i32,
+                };
+                for &i in &bins[ty * tiles_x + tx] {
+                    let (tri, b) = &tris[i as usize];
+                    if let Some(r) = b.intersect(rect) {
+                        raster_triangle(&mut target, r, tri, ops, &mut local, shade_pixel);
+                    }
+                }
+            }
+        }
+        local
+    };
+
+    let workers = worker_count(threads).min(tiles_y);
+    if workers <= 1 {
+        *stats += run();
+        return;
+    }
+    std::thread::scope(|s| {
+        let handles: Vec<_> = (0..workers).map(|_| s.spawn(run)).collect();
+        for h in handles {
+            *stats += h.join().expect("worker del rasterizador falló");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::renderer::pipeline::{draw_mesh, CullMode, PipelineState};
+    use crate::renderer::buffers::Msaa;
+    use crate::shaders::{make_shader, ShaderKind};
+    use crate::utils::testing::{looking_at_origin, sphere};
+
+    fn render(mode: RasterMode, msaa: Msaa) -> (Framebuffer, DrawStats) {
+        let mesh = sphere();
+        let (w, h) = (173, 97); // no múltiplo del tile
+        let mut fb = Framebuffer::with_msaa(w, h, msaa);
+        let (u, vp) = looking_at_origin(Vec3::new(0.3, 0.5, 1.6), w, h, 2.0);
+
+        let mut stats = DrawStats::default();
+        let opaque = PipelineState { cull: CullMode::Back, raster: mode, ..Default::default() };
+        let rings = PipelineState { raster: mode, ..PipelineState::transparent() };
+        stats += draw_mesh(&mut fb, &mesh, &mut *make_shader(ShaderKind::Gas), &u, vp, &opaque);
+        stats += draw_mesh(&mut fb, &mesh, &mut *make_shader(ShaderKind::Rings), &u, vp, &rings);
+        (fb, stats)
+    }
+
+    #[test]
+    fn tiled_matches_single_thread() {
+        for msaa in [Msaa::Off, Msaa::X4] {
+            let (a, sa) = render(RasterMode::Single, msaa);
+            let (b, sb) = render(RasterMode::Tiled { tile_size: 16, threads: 4 }, msaa);
+            assert!(a.color.iter().zip(&b.color).all(|(x, y)| x.to_rgba() == y.to_rgba()));
+            assert!(a.depth.iter().zip(&b.depth).all(|(x, y)| x.to_bits() == y.to_bits()));
+            assert_eq!(sa.fragments_shaded, sb.fragments_shaded);
+            assert_eq!(sa.fragments_early_rejected, sb.fragments_early_rejected);
+        }
+    }
+
+    #[test]
+    fn msaa_blends_silhouette_edges() {
+        // Flat es un color constante: sin MSAA solo hay fondo o planeta
+        let mesh = sphere();
+        let (u, vp) = looking_at_origin(Vec3::new(0.0, 0.0, 2.0), 64, 64, 0.0);
+        let edge_pixels = |msaa: Msaa| {
+            let mut fb = Framebuffer::with_msaa(64, 64, msaa);
+            draw_mesh(&mut fb, &mesh, &mut *make_shader(ShaderKind::Flat), &u, vp, &PipelineState::default());
+            fb.resolve().iter().filter(|c| c.r != 0 && c.r != 230).count()
+        };
+        assert_eq!(edge_pixels(Msaa::Off), 0);
+        assert!(edge_pixels(Msaa::X4) > 0);
+        assert!(edge_pixels(Msaa::X8) > 0);
+    }
+}
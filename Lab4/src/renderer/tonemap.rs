@@ -0,0 +1,95 @@
+use super::buffers::ColorF;
+
+/// Curva de tone mapping HDR -> [0,1]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Solo recorta (equivale al destino LDR)
+    Clamp,
+    /// x / (1 + x)
+    Reinhard,
+    /// Aproximación de la curva filmica ACES (Narkowicz 2015)
+    #[default]
+    Aces,
+}
+
+impl ToneMap {
+    #[inline]
+    fn curve(self, x: f32) -> f32 {
+        match self {
+            ToneMap::Clamp    => x,
+            ToneMap::Reinhard => x / (1.0 + x),
+            ToneMap::Aces     => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                (x*(a*x + b)) / (x*(c*x + d) + e)
+            }
+        }
+        .clamp(0.0, 1.0)
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "clamp" | "none" => Some(ToneMap::Clamp),
+            "reinhard"       => Some(ToneMap::Reinhard),
+            "aces"           => Some(ToneMap::Aces),
+            _ => None,
+        }
+    }
+}
+
+/// Resolve de un destino HDR: exposición (en stops) + curva
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ToneMapping {
+    pub op: ToneMap,
+    /// Multiplica la radiancia por 2^exposure antes de la curva
+    pub exposure: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self { Self { op: ToneMap::Aces, exposure: 0.0 } }
+}
+
+impl ToneMapping {
+    /// Radiancia lineal -> color de pantalla en [0,1]. El alpha solo se recorta.
+    #[inline]
+    pub fn map(&self, c: ColorF) -> ColorF {
+        let k = self.exposure.exp2();
+        ColorF::rgba(
+            self.op.curve(c.r.max(0.0) * k),
+            self.op.curve(c.g.max(0.0) * k),
+            self.op.curve(c.b.max(0.0) * k),
+            c.a.clamp(0.0, 1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+    use crate::renderer::buffers::{Framebuffer, ColorFormat, Msaa};
+    use crate::renderer::pipeline::{draw_mesh, PipelineState};
+    use crate::shaders::{make_shader, ShaderKind};
+    use crate::utils::testing::{looking_at_origin, sphere};
+
+    #[test]
+    fn hdr_clamp_matches_ldr() {
+        let mesh = sphere();
+        let (u, vp) = looking_at_origin(Vec3::new(0.0, 0.3, 1.8), 48, 48, 1.0);
+        let render = |format| {
+            let mut fb = Framebuffer::with_format(48, 48, Msaa::Off, format);
+            fb.tone = ToneMapping { op: ToneMap::Clamp, exposure: 0.0 };
+            draw_mesh(&mut fb, &mesh, &mut *make_shader(ShaderKind::Lava), &u, vp, &PipelineState::default());
+            fb.resolve().iter().map(|c| c.to_rgba()).collect::<Vec<_>>()
+        };
+        assert_eq!(render(ColorFormat::Ldr8), render(ColorFormat::HdrF32));
+    }
+
+    #[test]
+    fn curves_stay_in_range() {
+        for op in [ToneMap::Reinhard, ToneMap::Aces] {
+            let out = ToneMapping { op, exposure: 2.0 }.map(ColorF::rgb(0.0, 1.0, 50.0));
+            assert_eq!(out.r, 0.0);
+            assert!(out.g > 0.0 && out.g < out.b && out.b <= 1.0);
+        }
+    }
+}
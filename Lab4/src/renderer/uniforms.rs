@@ -1,4 +1,7 @@
+use std::sync::Arc;
+
 use crate::math::{Vec3, Mat4};
+use super::shadow::ShadowMap;
 
 #[derive(Copy, Clone, Debug)]
 pub struct PlanetParams {
@@ -6,7 +9,6 @@ pub struct PlanetParams {
     pub band_freq: f32,
     pub noise_scale: f32,
     pub rim_power: f32,
-    pub rotation_speed: f32,
     pub has_rings: bool,
     pub has_moon: bool,
 }
@@ -18,14 +20,14 @@ impl Default for PlanetParams {
             band_freq: 6.0,
             noise_scale: 2.0,
             rim_power: 2.0,
-            rotation_speed: 0.5,
             has_rings: false,
             has_moon: false,
         }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// `Clone` y no `Copy`: el shadow map se comparte por `Arc`
+#[derive(Clone, Debug)]
 pub struct Uniforms {
     pub time: f32,
     pub light_dir: Vec3,
@@ -34,6 +36,8 @@ pub struct Uniforms {
     pub model: Mat4,
     pub camera_pos: Vec3,
     pub planet: PlanetParams,
+    /// Shadow map de la luz direccional, si el frame tiene pase de sombras
+    pub shadow: Option<Arc<ShadowMap>>,
 }
 
 impl Default for Uniforms {
@@ -46,6 +50,7 @@ impl Default for Uniforms {
             model: Mat4::identity(),
             camera_pos: Vec3::new(0.0, 0.0, 3.0),
             planet: PlanetParams::default(),
+            shadow: None,
         }
     }
 }
@@ -0,0 +1,558 @@
+//! Escenas descritas en JSON:
+//!
+//! ```json
+//! {
+//!   "camera": { "eye": [0, 1, 6], "center": [0, 0, 0], "fov": 50 },
+//!   "light": { "direction": [0.5, 0.7, 0.2] },
+//!   "shadows": true,
+//!   "radius": 8,
+//!   "planets": [
+//!     { "name": "saturno", "shader": "gas", "params": { "band_freq": 8 },
+//!       "position": [-2, 0, 0], "scale": 0.9, "orbit": 0.3, "spin": 0.5,
+//!       "rings": { "params": { "tilt": 0.3 } },
+//!       "moons": [ { "shader": "moon", "position": [2, 0.4, 0], "scale": 0.3, "orbit": -0.5 } ] },
+//!     { "shader": "ice", "tilt": 23, "day": 10,
+//!       "orbit": { "semi_major": 5, "eccentricity": 0.2, "inclination": 8, "ascending_node": 30,
+//!                  "periapsis": 90, "period": 40, "phase": 0 } }
+//!   ]
+//! }
+//! ```
+//!
+//! Todo es opcional salvo el `shader` de cada cuerpo. Cada planeta es un nodo en `position`
+//! que orbita al padre con el cuerpo (`scale`, `tilt`, `spin`), los anillos y las lunas como
+//! hijos; las lunas son cuerpos con la misma forma. `orbit` es una velocidad (rad/s) de
+//! órbita circular por `position` o los elementos de una kepleriana (`Orbit`) con foco en
+//! `position`; `day` es el período de rotación sidéreo, alternativa a `spin`. Los ángulos de
+//! `tilt` y de las órbitas van en grados. `radius` acota la escena para el shadow map y la
+//! cámara; si falta se calcula con las posiciones y órbitas iniciales.
+//!
+//! Con `"gravity": { "g": 1, "dt": 0.005, "softening": 0.05 }` las órbitas las calcula
+//! `NBody`: cada cuerpo (también las lunas, con posición y velocidad relativas a su planeta)
+//! necesita `mass` y puede tener `velocity`, y no admite `orbit`. Los errores nombran el campo:
+//! `escena.json: planets[1].rings.params.tilt: se esperaba un número`.
+//!
+//! `SceneWatcher` vuelve a leer el archivo cuando cambia en disco (recarga en caliente).
+
+use std::f32::consts::TAU;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::math::{Mat4, Vec3};
+use crate::math::mat::{scale, translate};
+use crate::shaders::{Param, ShaderKind, ShaderParams};
+use crate::utils::json::Json;
+use super::graph::{MESH_RADIUS, Node, Scene};
+use super::nbody::{Body, GravitySettings, NBody};
+use super::orbit::Orbit;
+use super::showcase::{LIGHT_DIR, default_camera, pipeline_state};
+
+pub fn load_scene(path: &Path) -> Result<Scene, String> {
+    let name = path.display().to_string();
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{name}: {e}"))?;
+    parse_scene(&src, &name)
+}
+
+/// Recarga un archivo de escena cuando cambia en disco. Sondea la fecha de modificación
+/// y el tamaño como mucho una vez cada `interval`.
+#[derive(Clone, Debug)]
+pub struct SceneWatcher {
+    pub path: PathBuf,
+    pub interval: Duration,
+    stamp: Option<(SystemTime, u64)>,
+    checked: Option<Instant>,
+}
+
+impl SceneWatcher {
+    /// Toma como ya cargada la versión actual del archivo
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.into(), interval: Duration::from_millis(500), stamp: stamp(path), checked: None }
+    }
+
+    /// `None` si el archivo no cambió desde la última vez; si cambió, la escena nueva o el
+    /// error. Un archivo con errores no se vuelve a leer hasta que cambie otra vez.
+    pub fn poll(&mut self) -> Option<Result<Scene, String>> {
+        let now = Instant::now();
+        if self.checked.is_some_and(|t| now - t < self.interval) { return None; }
+        self.checked = Some(now);
+        let stamp = stamp(&self.path);
+        if stamp == self.stamp { return None; }
+        self.stamp = stamp;
+        Some(load_scene(&self.path))
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// `name` solo se usa en los mensajes de error
+pub fn parse_scene(src: &str, name: &str) -> Result<Scene, String> {
+    let doc = Json::parse(src).map_err(|e| format!("{name}:{e}"))?;
+    scene(&doc).map_err(|e| format!("{name}: {e}"))
+}
+
+fn scene(doc: &Json) -> Result<Scene, String> {
+    let fields = object(doc, "", &["camera", "light", "shadows", "radius", "gravity", "planets"])?;
+
+    let mut camera = default_camera(16.0 / 9.0);
+    if let Some(cam) = fields.get("camera") {
+        let c = object(cam, "camera", &["eye", "center", "fov"])?;
+        if let Some(v) = c.get("eye") { camera.eye = vec3(v, "camera.eye")?; }
+        if let Some(v) = c.get("center") { camera.center = vec3(v, "camera.center")?; }
+        if let Some(v) = c.get("fov") { camera.fov_y = in_range(v, "camera.fov", 1.0, 179.0)?.to_radians(); }
+        if (camera.eye - camera.center).length() < 1e-6 { return Err("camera: eye y center coinciden".into()); }
+    }
+
+    let mut light_dir = LIGHT_DIR;
+    if let Some(light) = fields.get("light") {
+        let l = object(light, "light", &["direction"])?;
+        if let Some(v) = l.get("direction") { light_dir = vec3(v, "light.direction")?; }
+        if light_dir.length() < 1e-6 { return Err("light.direction: no puede ser cero".into()); }
+    }
+
+    let shadows = match fields.get("shadows") {
+        Some(v) => v.as_bool().ok_or("shadows: se esperaba true o false")?,
+        None => true,
+    };
+
+    let settings = fields.get("gravity").map(gravity).transpose()?;
+    let mut free = settings.map(|_| Vec::new());
+    let mut planets = match fields.get("planets") {
+        Some(v) => bodies(v, "planets", &mut free, Body::default())?,
+        None => Vec::new(),
+    };
+    // Con gravedad todos los cuerpos cuelgan de la raíz, en el orden de la simulación
+    let gravity = settings.zip(free).map(|(settings, free)| {
+        let (nodes, states): (Vec<_>, Vec<_>) = free.into_iter().unzip();
+        planets = nodes;
+        NBody::new(settings, states)
+    });
+    let root = Node::group("scene", Mat4::identity(), planets);
+    // Con gravedad los cuerpos pueden salir de esta esfera; el archivo puede dar una mayor
+    let radius = match fields.get("radius") {
+        Some(v) => positive(v, "radius")?,
+        None => root.bounding_radius(MESH_RADIUS),
+    };
+    Ok(Scene { camera, light_dir: light_dir.normalize(), radius, shadows, root, gravity })
+}
+
+fn gravity(v: &Json) -> Result<GravitySettings, String> {
+    let f = object(v, "gravity", &["g", "dt", "softening"])?;
+    let mut settings = GravitySettings::default();
+    if let Some(v) = f.get("g") { settings.g = positive(v, "gravity.g")?; }
+    if let Some(v) = f.get("dt") { settings.dt = positive(v, "gravity.dt")?; }
+    if let Some(v) = f.get("softening") { settings.softening = number(v, "gravity.softening")?; }
+    if settings.softening < 0.0 { return Err("gravity.softening: no puede ser negativo".into()); }
+    Ok(settings)
+}
+
+/// Nodos de una lista de cuerpos. Con gravedad (`free` no vacío) los cuerpos van a `free`
+/// con su estado inicial en el mundo, relativo al del padre (`origin`), y no se devuelve nada.
+fn bodies(v: &Json, path: &str, free: &mut Option<Vec<(Node, Body)>>, origin: Body) -> Result<Vec<Node>, String> {
+    let items = v.as_array().ok_or_else(|| format!("{path}: se esperaba una lista"))?;
+    let mut nodes = Vec::new();
+    for (i, b) in items.iter().enumerate() {
+        nodes.extend(body(b, &format!("{path}[{i}]"), free, origin)?);
+    }
+    Ok(nodes)
+}
+
+/// Un planeta o luna: nodo del sistema con el cuerpo, los anillos y las lunas como hijos
+fn body(v: &Json, path: &str, free: &mut Option<Vec<(Node, Body)>>, origin: Body) -> Result<Option<Node>, String> {
+    let f = object(v, path, &["name", "shader", "params", "position", "scale", "orbit", "tilt", "spin", "day",
+                              "mass", "velocity", "rings", "moons"])?;
+    let at = |key: &str| format!("{path}.{key}");
+
+    let kind_name = f.get("shader").ok_or_else(|| format!("{path}: falta \"shader\""))?;
+    let kind = kind_name.as_str().and_then(ShaderKind::parse).ok_or_else(|| {
+        format!("{}: shader desconocido (usa rocky, gas, scifi, rings, moon, flat, lava o ice)", at("shader"))
+    })?;
+    let name = match f.get("name") {
+        Some(n) => n.as_str().ok_or_else(|| format!("{}: se esperaba un texto", at("name")))?.to_string(),
+        None => kind.name().to_string(),
+    };
+    let position = f.get("position").map(|v| vec3(v, &at("position"))).transpose()?.unwrap_or(Vec3::ZERO);
+    let s = f.get("scale").map(|v| positive(v, &at("scale"))).transpose()?.unwrap_or(1.0);
+    // Un número es una órbita circular por `position`; un objeto, una kepleriana con foco en `position`
+    let (transform, orbit) = match f.get("orbit") {
+        Some(v) if v.as_object().is_some() => (translate(position), Some(elements(v, &at("orbit"))?)),
+        Some(v) => {
+            let speed = number(v, &at("orbit"))?;
+            (translate(Vec3::new(0.0, position.y, 0.0)), Some(Orbit::through(position, speed)))
+        }
+        None => (translate(position), None),
+    };
+    let tilt = f.get("tilt").map(|v| number(v, &at("tilt"))).transpose()?.unwrap_or(0.0).to_radians();
+    let spin = match (f.get("spin"), f.get("day")) {
+        (Some(_), Some(_)) => return Err(format!("{path}: usa spin o day, no ambos")),
+        (Some(v), None) => number(v, &at("spin"))?,
+        (None, Some(v)) => TAU / nonzero(v, &at("day"))?,
+        (None, None) => 0.0,
+    };
+
+    let mut children = vec![Node {
+        name: name.clone(),
+        transform: scale(Vec3::new(s, s, s)),
+        tilt,
+        spin,
+        shader: Some(shader(kind, f.get("params"), &at("params"))?),
+        state: pipeline_state(kind),
+        ..Default::default()
+    }];
+    if let Some(rings) = f.get("rings") {
+        let path = at("rings");
+        let r = object(rings, &path, &["params", "scale", "spin"])?;
+        let rs = r.get("scale").map(|v| positive(v, &format!("{path}.scale"))).transpose()?.unwrap_or(s);
+        children.push(Node {
+            name: format!("{name}.rings"),
+            transform: scale(Vec3::new(rs, rs, rs)),
+            tilt,
+            spin: r.get("spin").map(|v| number(v, &format!("{path}.spin"))).transpose()?.unwrap_or(spin),
+            shader: Some(shader(ShaderKind::Rings, r.get("params"), &format!("{path}.params"))?),
+            state: pipeline_state(ShaderKind::Rings),
+            ..Default::default()
+        });
+    }
+    let mass = f.get("mass").map(|v| positive(v, &at("mass"))).transpose()?;
+    let velocity = f.get("velocity").map(|v| vec3(v, &at("velocity"))).transpose()?;
+    if free.is_none() {
+        if mass.is_some() || velocity.is_some() { return Err(format!("{path}: mass y velocity solo valen con \"gravity\"")); }
+        if let Some(moons) = f.get("moons") {
+            children.extend(bodies(moons, &at("moons"), free, origin)?);
+        }
+        return Ok(Some(Node { orbit, ..Node::group(&name, transform, children) }));
+    }
+
+    // Con gravedad la posición la da la simulación
+    if orbit.is_some() { return Err(format!("{}: no se combina con \"gravity\"", at("orbit"))); }
+    let state = Body {
+        mass: mass.ok_or_else(|| format!("{path}: falta \"mass\" (la escena tiene \"gravity\")"))?,
+        position: origin.position + position,
+        velocity: origin.velocity + velocity.unwrap_or(Vec3::ZERO),
+    };
+    if let Some(free) = free { free.push((Node::group(&name, translate(state.position), children), state)); }
+    if let Some(moons) = f.get("moons") {
+        bodies(moons, &at("moons"), free, state)?;
+    }
+    Ok(None)
+}
+
+/// Elementos keplerianos de una órbita; los ángulos van en grados
+fn elements(v: &Json, path: &str) -> Result<Orbit, String> {
+    let f = object(v, path, &["semi_major", "eccentricity", "inclination", "ascending_node", "periapsis", "period", "phase"])?;
+    let at = |key: &str| format!("{path}.{key}");
+    let required = |key: &str| f.get(key).ok_or_else(|| format!("{path}: falta \"{key}\""));
+    let angle = |key: &str| f.get(key).map(|v| number(v, &at(key))).transpose().map(|a| a.unwrap_or(0.0).to_radians());
+    Ok(Orbit {
+        semi_major: positive(required("semi_major")?, &at("semi_major"))?,
+        eccentricity: f.get("eccentricity").map(|v| in_range(v, &at("eccentricity"), 0.0, 0.99)).transpose()?.unwrap_or(0.0),
+        inclination: angle("inclination")?,
+        ascending_node: angle("ascending_node")?,
+        periapsis: angle("periapsis")?,
+        period: nonzero(required("period")?, &at("period"))?,
+        mean_anomaly: angle("phase")?,
+    })
+}
+
+/// Parámetros por defecto de `kind` con los de `params` encima
+fn shader(kind: ShaderKind, params: Option<&Json>, path: &str) -> Result<ShaderParams, String> {
+    let mut shader = ShaderParams::new(kind);
+    let Some(params) = params else { return Ok(shader) };
+    let given = params.as_object().ok_or_else(|| format!("{path}: se esperaba un objeto"))?;
+    unique(given, &format!("{path}."))?;
+    let mut fields = shader.fields();
+    for (key, value) in given {
+        let at = format!("{path}.{key}");
+        let Some((_, field)) = fields.iter_mut().find(|(k, _)| k == key) else {
+            let known: Vec<&str> = fields.iter().map(|(k, _)| *k).collect();
+            return Err(if known.is_empty() {
+                format!("{at}: {} no tiene parámetros", kind.name())
+            } else {
+                format!("{at}: parámetro desconocido de {} (usa {})", kind.name(), known.join(", "))
+            });
+        };
+        match field {
+            Param::Number(n) => **n = number(value, &at)?,
+            Param::Vec3(c) => **c = vec3(value, &at)?,
+        }
+    }
+    Ok(shader)
+}
+
+/// Campos de un objeto, con error si hay claves fuera de `known` (erratas) o repetidas
+fn object<'a>(v: &'a Json, path: &str, known: &[&str]) -> Result<Fields<'a>, String> {
+    let prefix = if path.is_empty() { String::new() } else { format!("{path}.") };
+    let fields = v.as_object().ok_or_else(|| format!("{}: se esperaba un objeto", if path.is_empty() { "raíz" } else { path }))?;
+    for (key, _) in fields {
+        if !known.contains(&key.as_str()) {
+            return Err(format!("{prefix}{key}: campo desconocido (usa {})", known.join(", ")));
+        }
+    }
+    unique(fields, &prefix)?;
+    Ok(Fields(fields))
+}
+
+/// Error en la segunda aparición de una clave: si no, `Fields::get` ignoraría su valor
+fn unique(fields: &[(String, Json)], prefix: &str) -> Result<(), String> {
+    for (i, (key, _)) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|(k, _)| k == key) {
+            return Err(format!("{prefix}{key}: campo repetido"));
+        }
+    }
+    Ok(())
+}
+
+struct Fields<'a>(&'a [(String, Json)]);
+
+impl<'a> Fields<'a> {
+    fn get(&self, key: &str) -> Option<&'a Json> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+}
+
+fn number(v: &Json, path: &str) -> Result<f32, String> {
+    v.as_f64().map(|n| n as f32).filter(|n| n.is_finite()).ok_or_else(|| format!("{path}: se esperaba un número"))
+}
+
+fn positive(v: &Json, path: &str) -> Result<f32, String> {
+    let n = number(v, path)?;
+    if n > 0.0 { Ok(n) } else { Err(format!("{path}: debe ser mayor que 0")) }
+}
+
+fn nonzero(v: &Json, path: &str) -> Result<f32, String> {
+    let n = number(v, path)?;
+    if n != 0.0 { Ok(n) } else { Err(format!("{path}: no puede ser 0")) }
+}
+
+fn in_range(v: &Json, path: &str, lo: f32, hi: f32) -> Result<f32, String> {
+    let n = number(v, path)?;
+    if (lo..=hi).contains(&n) { Ok(n) } else { Err(format!("{path}: debe estar entre {lo} y {hi}")) }
+}
+
+fn vec3(v: &Json, path: &str) -> Result<Vec3, String> {
+    match v.as_array() {
+        Some([x, y, z]) => Ok(Vec3::new(number(x, path)?, number(y, path)?, number(z, path)?)),
+        _ => Err(format!("{path}: se esperaba [x, y, z]")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn builds_graph_and_names_bad_fields() {
+        let src = r#"{
+            "camera": { "eye": [0, 2, 8], "fov": 45 },
+            "light": { "direction": [0, 2, 0] },
+            "planets": [
+                { "shader": "rocky", "params": { "sand": [1, 0.5, 0], "displacement": 0 },
+                  "position": [3, 0, 0], "scale": 0.5, "orbit": 0.2,
+                  "moons": [ { "name": "fobos", "shader": "moon", "position": [1, 0, 0], "scale": 0.2 } ] },
+                { "name": "saturno", "shader": "gas", "spin": 0.3, "rings": { "params": { "tilt": 0.1 } } }
+            ]
+        }"#;
+        let scene = parse_scene(src, "s.json").unwrap();
+        assert_eq!(scene.camera.eye, Vec3::new(0.0, 2.0, 8.0));
+        assert!((scene.camera.fov_y - 45f32.to_radians()).abs() < 1e-6);
+        assert_eq!(scene.light_dir, Vec3::new(0.0, 1.0, 0.0));
+        // Luna a 3 + 1 del origen, con radio 0.2 * 1.6 (cota de los anillos para toda malla)
+        assert!((scene.radius - 4.32).abs() < 1e-5 && scene.shadows);
+
+        let rocky = &scene.root.children[0];
+        assert_eq!((rocky.name.as_str(), rocky.orbit, rocky.children.len()), ("rocky", Some(Orbit::through(Vec3::new(3.0, 0.0, 0.0), 0.2)), 2));
+        let Some(ShaderParams::Rocky(r)) = rocky.children[0].shader else { panic!("{:?}", rocky.children[0].shader) };
+        assert_eq!((r.sand, r.displacement), (Vec3::new(1.0, 0.5, 0.0), 0.0));
+        assert_eq!(rocky.children[1].name, "fobos");
+        let Some((ShaderParams::Rings(rings), _)) = scene.root.find_shader(ShaderKind::Rings, 0.0) else { panic!() };
+        assert_eq!(rings.tilt, 0.1);
+        assert_eq!(scene.root.find(0.0, |n| n.name == "saturno.rings").unwrap().0.spin, 0.3);
+
+        let err = |src: &str| parse_scene(src, "s.json").unwrap_err();
+        assert_eq!(err(r#"{"planets": [{"shader": "gas"}, {"shader": "gas", "params": {"band_freq": "x"}}]}"#),
+            "s.json: planets[1].params.band_freq: se esperaba un número");
+        assert_eq!(err(r#"{"planets": [{"shader": "ice", "rings": {"params": {"tilt": 1, "width": 2}}}]}"#),
+            "s.json: planets[0].rings.params.width: parámetro desconocido de rings (usa inner, outer, tilt)");
+        assert!(err(r#"{"planets": [{"shader": "pluto"}]}"#).starts_with("s.json: planets[0].shader: shader desconocido"));
+        assert_eq!(err(r#"{"planets": [{"shader": "gas", "moons": [{"scale": 1}]}]}"#), "s.json: planets[0].moons[0]: falta \"shader\"");
+        assert_eq!(err(r#"{"planets": [{"shader": "gas", "scale": 0}]}"#), "s.json: planets[0].scale: debe ser mayor que 0");
+        assert_eq!(err(r#"{"camera": {"eye": [1, 2]}}"#), "s.json: camera.eye: se esperaba [x, y, z]");
+        assert!(err(r#"{"planet": []}"#).starts_with("s.json: planet: campo desconocido"));
+        assert_eq!(err(r#"{"planets": [{"shader": "gas", "scale": 1, "scale": 2}]}"#), "s.json: planets[0].scale: campo repetido");
+        assert_eq!(err(r#"{"planets": [{"shader": "gas", "params": {"band_freq": 1, "band_freq": 2}}]}"#),
+            "s.json: planets[0].params.band_freq: campo repetido");
+        assert_eq!(err("{\n  \"shadows\": tru\n}"), "s.json:2:14: valor inválido");
+    }
+
+    #[test]
+    fn spinning_body_keeps_its_surface() {
+        use crate::math::{Vec2, Vec4};
+        use crate::renderer::Uniforms;
+        use crate::renderer::pipeline::VertexIn;
+        use crate::renderer::raster::Varyings;
+
+        let src = r#"{"planets": [
+            { "shader": "gas", "day": 7, "tilt": 20, "orbit": { "semi_major": 3, "eccentricity": 0.3, "period": 30 } },
+            { "shader": "lava", "day": 5, "position": [0, 0, 4], "orbit": 0.4 },
+            { "shader": "ice", "spin": 0.9, "tilt": -35, "position": [-5, 0, 0] },
+            { "shader": "scifi", "day": 9, "position": [6, 1, 0], "orbit": -0.2 },
+            { "shader": "lava", "moons": [ { "shader": "moon", "day": 3, "position": [2, 0, 0], "orbit": 0.5 } ] }
+        ]}"#;
+        let scene = parse_scene(src, "s.json").unwrap();
+        let dir = Vec3::new(0.3, 0.5, 0.8).normalize();
+        let vin = VertexIn { pos: dir, nrm: dir, uv: Vec2::ZERO, tangent: Vec4::ZERO };
+        let point = |m: Mat4, p: Vec3| (m * Vec4::from3(p, 1.0)).xyz();
+
+        for kind in [ShaderKind::Gas, ShaderKind::Lava, ShaderKind::Ice, ShaderKind::SciFi, ShaderKind::Moon] {
+            // Luz y cámara fijas respecto del cuerpo y el mismo `time` en los uniforms: solo
+            // cambia la transformación del nodo, así que el color del punto no debe cambiar
+            let shade = |t: f32| {
+                let (params, model) = scene.root.find_shader(kind, t).unwrap();
+                let light_dir = (model * Vec4::new(0.2, 0.9, 0.4, 0.0)).xyz().normalize();
+                let u = Uniforms { model, light_dir, camera_pos: point(model, Vec3::new(0.0, 1.0, 4.0)), ..Default::default() };
+                let mut shader = params.build();
+                let out = shader.vertex(vin, &u);
+                let c = shader.fragment(&Varyings { pos_ws: out.pos_ws, nrm_ws: out.nrm_ws, uv: out.uv, pos_os: out.pos_os }, &u);
+                (c, point(model, dir) - point(model, Vec3::ZERO))
+            };
+            let ((a, at_a), (b, at_b)) = (shade(0.0), shade(2.5));
+            // El punto sí giró en el mundo...
+            assert!((at_a - at_b).length() > 0.1, "{kind:?} no gira");
+            // ...y la superficie giró con él
+            let d = (a.r - b.r).abs().max((a.g - b.g).abs()).max((a.b - b.b).abs());
+            assert!(d < 1e-3, "{kind:?}: {a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn example_scene_loads() {
+        let path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/system.json"));
+        let scene = load_scene(path).unwrap();
+        let drawn = |kind| scene.root.find_shader(kind, 0.0).is_some();
+        assert!([ShaderKind::Gas, ShaderKind::Rings, ShaderKind::Rocky, ShaderKind::Moon, ShaderKind::Lava, ShaderKind::Ice].into_iter().all(drawn));
+    }
+
+    #[test]
+    fn gravity_example_keeps_its_moon() {
+        // En el ejemplo de gravedad la luna sigue ligada a su planeta tras unas vueltas
+        let path = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/gravity.json"));
+        let mut scene = load_scene(path).unwrap();
+        scene.advance(40.0);
+        let sim = scene.gravity.as_ref().unwrap();
+        let at = |name: &str| sim.bodies()[scene.root.children.iter().position(|n| n.name == name).unwrap()].position;
+        assert!((at("fobos") - at("marte")).length() < 0.5);
+        assert!(sim.max_energy_drift() < 1e-3, "{}", sim.max_energy_drift());
+    }
+
+    #[test]
+    fn watcher_reloads_only_when_the_file_changes() {
+        let dir = temp_dir("watch");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("scene.json");
+        std::fs::write(&path, r#"{"planets": [{"shader": "gas"}]}"#).unwrap();
+
+        let mut watcher = SceneWatcher { interval: Duration::ZERO, ..SceneWatcher::new(&path) };
+        assert!(watcher.poll().is_none());
+
+        // Los tamaños distintos garantizan el cambio aunque la fecha tenga poca resolución
+        std::fs::write(&path, r#"{"planets": [{"shader": "rocky", "scale": 2}]}"#).unwrap();
+        let scene = watcher.poll().unwrap().unwrap();
+        assert!(scene.root.find_shader(ShaderKind::Rocky, 0.0).is_some());
+        assert!(watcher.poll().is_none());
+
+        std::fs::write(&path, r#"{"planets": [{"shader": "rocky", "scale": -2}]}"#).unwrap();
+        assert!(watcher.poll().unwrap().unwrap_err().ends_with("planets[0].scale: debe ser mayor que 0"));
+        assert!(watcher.poll().is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn keplerian_orbit_tilt_and_day() {
+        let src = r#"{"planets": [
+            { "name": "hielo", "shader": "ice", "position": [0, 1, 0], "tilt": 90, "day": 4, "rings": {},
+              "orbit": { "semi_major": 2, "eccentricity": 0.5, "periapsis": 90, "period": 10 } }
+        ]}"#;
+        let scene = parse_scene(src, "s.json").unwrap();
+        let planet = &scene.root.children[0];
+        let orbit = planet.orbit.unwrap();
+        assert_eq!((orbit.semi_major, orbit.eccentricity, orbit.period), (2.0, 0.5, 10.0));
+        assert!((orbit.periapsis - FRAC_PI_2).abs() < 1e-6);
+        let body = &planet.children[0];
+        assert!((body.tilt - FRAC_PI_2).abs() < 1e-6 && (body.spin - TAU / 4.0).abs() < 1e-6);
+        assert_eq!(planet.children[1].tilt, body.tilt);
+
+        // Periapsis a 1 del foco (en `position`), girado 90° hacia -z; el radio cubre el apoapsis
+        let (_, world) = scene.root.find_shader(ShaderKind::Ice, 0.0).unwrap();
+        let center = Vec3::new(world.m[0][3], world.m[1][3], world.m[2][3]);
+        assert!((center - Vec3::new(0.0, 1.0, -1.0)).length() < 1e-5, "{center:?}");
+        assert!((scene.radius - (1.0 + 3.0 + MESH_RADIUS)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn names_bad_orbits_and_days() {
+        let err = |src: &str| parse_scene(src, "s.json").unwrap_err();
+        assert_eq!(err(r#"{"planets": [{"shader": "ice", "orbit": {"period": 3}}]}"#), "s.json: planets[0].orbit: falta \"semi_major\"");
+        assert_eq!(err(r#"{"planets": [{"shader": "ice", "orbit": {"semi_major": 2, "period": 3, "eccentricity": 1}}]}"#),
+            "s.json: planets[0].orbit.eccentricity: debe estar entre 0 y 0.99");
+        assert_eq!(err(r#"{"planets": [{"shader": "ice", "spin": 1, "day": 2}]}"#), "s.json: planets[0]: usa spin o day, no ambos");
+    }
+
+    #[test]
+    fn gravity_frees_moons_and_moves_the_nodes() {
+        let src = r#"{
+            "gravity": { "dt": 0.01, "softening": 0 },
+            "planets": [
+                { "name": "sol", "shader": "lava", "mass": 1, "scale": 0.5 },
+                { "shader": "rocky", "mass": 0.001, "position": [2, 0, 0], "velocity": [0, 0, -0.7],
+                  "moons": [ { "shader": "moon", "mass": 1e-5, "position": [0.2, 0, 0], "velocity": [0, 0, -0.07] } ] }
+            ]
+        }"#;
+        let mut scene = parse_scene(src, "s.json").unwrap();
+        let names: Vec<_> = scene.root.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["sol", "rocky", "moon"]);
+        let sim = scene.gravity.as_ref().unwrap();
+        assert_eq!(sim.settings().dt, 0.01);
+        // La luna parte en el mundo sumando posición y velocidad del planeta
+        assert_eq!(sim.bodies()[2].position, Vec3::new(2.2, 0.0, 0.0));
+        assert!((sim.bodies()[2].velocity - Vec3::new(0.0, 0.0, -0.77)).length() < 1e-6);
+
+        scene.advance(1.0);
+        let sim = scene.gravity.as_ref().unwrap();
+        assert_eq!(sim.steps(), 100);
+        let (_, world) = scene.root.find_shader(ShaderKind::Rocky, 1.0).unwrap();
+        assert_eq!(Vec3::new(world.m[0][3], world.m[1][3], world.m[2][3]), sim.bodies()[1].position);
+        assert!(sim.bodies()[1].position.z < -0.5);
+
+        let err = |src: &str| parse_scene(src, "s.json").unwrap_err();
+        assert_eq!(err(r#"{"gravity": {}, "planets": [{"shader": "gas"}]}"#), "s.json: planets[0]: falta \"mass\" (la escena tiene \"gravity\")");
+        assert_eq!(err(r#"{"gravity": {}, "planets": [{"shader": "gas", "mass": 1, "orbit": 0.3}]}"#),
+            "s.json: planets[0].orbit: no se combina con \"gravity\"");
+        assert_eq!(err(r#"{"planets": [{"shader": "gas", "mass": 1}]}"#), "s.json: planets[0]: mass y velocity solo valen con \"gravity\"");
+        assert_eq!(err(r#"{"gravity": {"dt": 0}}"#), "s.json: gravity.dt: debe ser mayor que 0");
+    }
+
+    #[test]
+    fn radius_is_fixed_at_load_or_given() {
+        let src = r#"{
+            "gravity": { "dt": 0.01 },
+            "planets": [
+                { "shader": "lava", "mass": 1 },
+                { "shader": "ice", "mass": 0.001, "position": [2, 0, 0], "velocity": [0, 0, -0.7] }
+            ]
+        }"#;
+        // El radio es el de la carga aunque los cuerpos se muevan
+        let mut scene = parse_scene(src, "s.json").unwrap();
+        let loaded = scene.radius;
+        assert!((loaded - (2.0 + MESH_RADIUS)).abs() < 1e-5);
+        scene.advance(3.0);
+        assert_eq!(scene.radius, loaded);
+
+        let bounded = parse_scene(&src.replacen('{', r#"{ "radius": 12,"#, 1), "s.json").unwrap();
+        assert_eq!(bounded.radius, 12.0);
+        assert_eq!(parse_scene(r#"{"radius": -1}"#, "s.json").unwrap_err(), "s.json: radius: debe ser mayor que 0");
+    }
+}
@@ -0,0 +1,527 @@
+use std::path::{Path, PathBuf};
+
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
+use crate::math::mat::{scale, translate};
+use crate::utils::json::Json;
+use super::model::{Material, Mesh, SubMesh, Triangle, Vertex};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+/// Carga un glTF 2.0: `.gltf` (buffers en `.bin` aparte o embebidos en base64) o `.glb`.
+/// - De cada primitiva de triángulos se leen `POSITION`, `NORMAL`, `TEXCOORD_0` e índices;
+///   puntos y líneas se ignoran.
+/// - Las mallas se instancian según los nodos de la escena, con su transformación
+///   acumulada (`matrix` o `translation`/`rotation`/`scale`) aplicada a posiciones y normales.
+/// - Cada primitiva instanciada es una `SubMesh` con el nombre del nodo (o de la malla), y
+///   `baseColorFactor`/`baseColorTexture` pasan a `Material`.
+/// - glTF pone v = 0 arriba; se invierte para que coincida con las UV de los `.obj`.
+///
+/// Los errores indican el archivo y el objeto del glTF (`accessors[3]`, `nodes[1]`, ...).
+pub fn load_gltf(path: &str) -> Result<Mesh, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("No pude abrir {}: {}", path, e))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    parse_gltf(&bytes, path, dir)
+}
+
+/// Igual que `load_gltf` con el archivo ya leído; reconoce `.glb` por la cabecera.
+/// `dir` es la carpeta contra la que se resuelven los `uri`.
+pub fn parse_gltf(bytes: &[u8], name: &str, dir: &Path) -> Result<Mesh, String> {
+    let err = |msg: String| format!("{name}: {msg}");
+    let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes).map_err(err)?
+    } else {
+        (std::str::from_utf8(bytes).map_err(|_| err("el JSON no es UTF-8".into()))?, None)
+    };
+    let doc = Json::parse(json).map_err(|e| err(format!("JSON {e}")))?;
+    let version = doc.get("asset").and_then(|a| a.get("version")).and_then(Json::as_str).unwrap_or("");
+    if !version.starts_with("2.") { return Err(err(format!("versión de glTF '{version}' no soportada (se espera 2.x)"))); }
+
+    let gltf = Gltf { buffers: load_buffers(&doc, dir, bin).map_err(err)?, doc, dir };
+    gltf.mesh().map_err(err)
+}
+
+/// Separa los chunks JSON y BIN de un `.glb`
+fn split_glb(bytes: &[u8]) -> Result<(&str, Option<&[u8]>), String> {
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let version = u32_at(4).ok_or("glb truncado")?;
+    if version != 2 { return Err(format!("glb versión {version} no soportada")); }
+    let total = (u32_at(8).ok_or("glb truncado")? as usize).min(bytes.len());
+
+    let (mut json, mut bin) = (None, None);
+    let mut at = 12;
+    while at + 8 <= total {
+        let (len, kind) = (u32_at(at).unwrap_or(0) as usize, u32_at(at + 4).unwrap_or(0));
+        let data = bytes.get(at + 8..at + 8 + len).ok_or_else(|| format!("glb: chunk en el byte {at} truncado"))?;
+        match kind {
+            CHUNK_JSON if json.is_none() => json = Some(std::str::from_utf8(data).map_err(|_| "glb: el JSON no es UTF-8")?),
+            CHUNK_BIN if bin.is_none() => bin = Some(data),
+            // Extensiones: se ignoran
+            _ => {}
+        }
+        at += 8 + len.next_multiple_of(4);
+    }
+    Ok((json.ok_or("glb sin chunk JSON")?, bin))
+}
+
+/// Contenido de cada `buffers[i]`: chunk BIN del `.glb`, data URI o archivo junto al glTF
+fn load_buffers(doc: &Json, dir: &Path, bin: Option<&[u8]>) -> Result<Vec<Vec<u8>>, String> {
+    let mut out = Vec::new();
+    for (i, buf) in array(doc, "buffers").iter().enumerate() {
+        let ctx = |msg: String| format!("buffers[{i}]: {msg}");
+        let len = buf.get("byteLength").and_then(Json::as_usize).ok_or_else(|| ctx("falta byteLength".into()))?;
+        let data = match buf.get("uri").and_then(Json::as_str) {
+            None => bin.ok_or_else(|| ctx("sin uri y el archivo no es .glb".into()))?.to_vec(),
+            Some(uri) => read_uri(uri, dir).map_err(ctx)?,
+        };
+        if data.len() < len { return Err(ctx(format!("tiene {} bytes, se esperaban {len}", data.len()))); }
+        out.push(data);
+    }
+    Ok(out)
+}
+
+fn read_uri(uri: &str, dir: &Path) -> Result<Vec<u8>, String> {
+    if let Some(rest) = uri.strip_prefix("data:") {
+        let (_, payload) = rest.split_once(";base64,").ok_or("solo se soportan data URI en base64")?;
+        return base64_decode(payload);
+    }
+    let path = uri_path(uri, dir);
+    std::fs::read(&path).map_err(|e| format!("No pude abrir {}: {}", path.display(), e))
+}
+
+/// `uri` relativa (con escapes `%XX`) a ruta
+fn uri_path(uri: &str, dir: &Path) -> PathBuf {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => { out.push(b); i += 3; }
+            (b, _) => { out.push(b); i += 1; }
+        }
+    }
+    dir.join(String::from_utf8_lossy(&out).as_ref())
+}
+
+fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in s.bytes().take_while(|&c| c != b'=') {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("carácter '{}' inválido en base64", c as char)),
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
+
+/// `doc[key]` como lista (vacía si falta)
+fn array<'a>(doc: &'a Json, key: &str) -> &'a [Json] {
+    doc.get(key).and_then(Json::as_array).unwrap_or(&[])
+}
+
+/// `N` floats de `doc[key]`, o `default` si falta
+fn floats<const N: usize>(doc: &Json, key: &str, default: [f32; N]) -> Result<[f32; N], String> {
+    let Some(v) = doc.get(key) else { return Ok(default) };
+    let items = v.as_array().filter(|a| a.len() == N).ok_or_else(|| format!("{key} debe tener {N} números"))?;
+    let mut out = default;
+    for (o, item) in out.iter_mut().zip(items) {
+        *o = item.as_f64().ok_or_else(|| format!("{key} debe tener {N} números"))? as f32;
+    }
+    Ok(out)
+}
+
+/// Malla a instanciar: índice en `meshes`, nodo (si hay) y matriz de mundo
+type Instance<'a> = (usize, Option<&'a Json>, Mat4);
+
+struct Gltf<'a> {
+    doc: Json,
+    dir: &'a Path,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Gltf<'_> {
+    /// `doc[key][i]` o un error que nombra la referencia rota
+    fn item(&self, key: &str, i: usize) -> Result<&Json, String> {
+        array(&self.doc, key).get(i).ok_or_else(|| format!("{key}[{i}] no existe"))
+    }
+
+    fn mesh(&self) -> Result<Mesh, String> {
+        let mut mesh = Mesh::default();
+        for (i, m) in array(&self.doc, "materials").iter().enumerate() {
+            mesh.materials.push(self.material(m).map_err(|e| format!("materials[{i}]: {e}"))?);
+        }
+
+        let mut has_normal = Vec::new();
+        let mut has_uv = false;
+        for (m, node, world) in self.instances()? {
+            let mesh_json = self.item("meshes", m)?;
+            let label = node.and_then(|n| n.get("name")).or_else(|| mesh_json.get("name")).and_then(Json::as_str)
+                .map_or_else(|| format!("mesh{m}"), str::to_string);
+
+            for (p, prim) in array(mesh_json, "primitives").iter().enumerate() {
+                let ctx = |e: String| format!("meshes[{m}].primitives[{p}]: {e}");
+                let start = mesh.indices.len();
+                let uv = self.primitive(prim, world, &mut mesh, &mut has_normal).map_err(ctx)?;
+                has_uv |= uv;
+                if mesh.indices.len() == start { continue; }
+                let material = prim.get("material").and_then(Json::as_usize).filter(|&i| i < mesh.materials.len());
+                mesh.submeshes.push(SubMesh { name: label.clone(), triangles: start..mesh.indices.len(), material });
+            }
+        }
+
+        if has_normal.iter().any(|h| !h) {
+            let given: Vec<Vec3> = mesh.vertices.iter().map(|v| v.nrm).collect();
+            mesh.recompute_normals();
+            for ((v, &h), n) in mesh.vertices.iter_mut().zip(&has_normal).zip(given) {
+                if h { v.nrm = n; }
+            }
+        }
+        if has_uv { mesh.recompute_tangents(); }
+        Ok(mesh)
+    }
+
+    /// Mallas a instanciar: índice de malla, nodo y matriz de mundo, según la escena activa.
+    /// Sin `scenes` se usan los nodos raíz; sin `nodes`, cada malla una vez sin transformar.
+    fn instances(&self) -> Result<Vec<Instance<'_>>, String> {
+        let nodes = array(&self.doc, "nodes");
+        if nodes.is_empty() {
+            return Ok((0..array(&self.doc, "meshes").len()).map(|m| (m, None, Mat4::identity())).collect());
+        }
+
+        let index_list = |v: &Json, what: &str| -> Result<Vec<usize>, String> {
+            v.as_array().unwrap_or(&[]).iter()
+                .map(|i| i.as_usize().filter(|&i| i < nodes.len()).ok_or_else(|| format!("{what}: índice de nodo inválido")))
+                .collect()
+        };
+        let roots = match self.doc.get("scenes").and_then(Json::as_array).filter(|s| !s.is_empty()) {
+            Some(scenes) => {
+                let s = self.doc.get("scene").and_then(Json::as_usize).unwrap_or(0);
+                let scene = scenes.get(s).ok_or_else(|| format!("scenes[{s}] no existe"))?;
+                index_list(scene.get("nodes").unwrap_or(&Json::Null), &format!("scenes[{s}].nodes"))?
+            }
+            None => {
+                let mut is_child = vec![false; nodes.len()];
+                for (i, n) in nodes.iter().enumerate() {
+                    for c in index_list(n.get("children").unwrap_or(&Json::Null), &format!("nodes[{i}].children"))? {
+                        is_child[c] = true;
+                    }
+                }
+                (0..nodes.len()).filter(|&i| !is_child[i]).collect()
+            }
+        };
+
+        let mut out = Vec::new();
+        let mut stack: Vec<(usize, Mat4, usize)> = roots.into_iter().rev().map(|r| (r, Mat4::identity(), 0)).collect();
+        while let Some((i, parent, depth)) = stack.pop() {
+            // Un nodo no puede ser su propio ancestro
+            if depth > nodes.len() { return Err(format!("nodes[{i}]: la jerarquía tiene un ciclo")); }
+            let node = &nodes[i];
+            let world = parent * node_matrix(node).map_err(|e| format!("nodes[{i}]: {e}"))?;
+            if let Some(m) = node.get("mesh") {
+                let m = m.as_usize().ok_or_else(|| format!("nodes[{i}]: mesh debe ser un índice"))?;
+                out.push((m, Some(node), world));
+            }
+            let children = index_list(node.get("children").unwrap_or(&Json::Null), &format!("nodes[{i}].children"))?;
+            stack.extend(children.into_iter().rev().map(|c| (c, world, depth + 1)));
+        }
+        Ok(out)
+    }
+
+    /// Agrega los triángulos de una primitiva; devuelve si trae UV
+    fn primitive(&self, prim: &Json, world: Mat4, mesh: &mut Mesh, has_normal: &mut Vec<bool>) -> Result<bool, String> {
+        let mode = prim.get("mode").and_then(Json::as_usize).unwrap_or(4);
+        if !(4..=6).contains(&mode) { return Ok(false); }
+
+        let attrs = prim.get("attributes").ok_or("faltan attributes")?;
+        let attr = |key: &str| attrs.get(key).map(|a| a.as_usize().ok_or_else(|| format!("{key}: índice inválido"))).transpose();
+        let pos_acc = attr("POSITION")?.ok_or("falta POSITION")?;
+        let positions = self.accessor(pos_acc, 3)?;
+        let count = positions.len() / 3;
+        let normals = attr("NORMAL")?.map(|a| self.accessor(a, 3)).transpose()?;
+        let uvs = attr("TEXCOORD_0")?.map(|a| self.accessor(a, 2)).transpose()?;
+        for (what, data, n) in [("NORMAL", &normals, 3), ("TEXCOORD_0", &uvs, 2)] {
+            if data.as_ref().is_some_and(|d| d.len() != count * n) {
+                return Err(format!("{what} tiene otra cantidad de elementos que POSITION"));
+            }
+        }
+
+        let indices: Vec<u32> = match prim.get("indices") {
+            Some(a) => {
+                let a = a.as_usize().ok_or("indices: índice inválido")?;
+                let idx = self.accessor_raw(a, 1)?;
+                if let Some(bad) = idx.iter().find(|&&i| i as usize >= count) {
+                    return Err(format!("índice {bad} fuera de rango (hay {count} vértices)"));
+                }
+                idx.into_iter().map(|i| i as u32).collect()
+            }
+            None => (0..count as u32).collect(),
+        };
+
+        // Normales: matriz de cofactores (inversa transpuesta por el determinante)
+        let [c0, c1, c2] = [0, 1, 2].map(|j| Vec3::new(world.m[0][j], world.m[1][j], world.m[2][j]));
+        let det = c0.dot(c1.cross(c2));
+        let flip = det < 0.0;
+        let (n0, n1, n2) = (c1.cross(c2), c2.cross(c0), c0.cross(c1));
+
+        let base = mesh.vertices.len() as u32;
+        for i in 0..count {
+            let p = Vec3::new(positions[3 * i], positions[3 * i + 1], positions[3 * i + 2]);
+            let nrm = normals.as_ref().map_or(Vec3::ZERO, |n| {
+                let v = n0 * n[3 * i] + n1 * n[3 * i + 1] + n2 * n[3 * i + 2];
+                (if flip { -v } else { v }).normalize()
+            });
+            let uv = uvs.as_ref().map_or(Vec2::ZERO, |t| Vec2::new(t[2 * i], 1.0 - t[2 * i + 1]));
+            mesh.vertices.push(Vertex { pos: (world * Vec4::from3(p, 1.0)).xyz(), nrm, uv, ..Default::default() });
+            has_normal.push(normals.is_some());
+        }
+
+        let mut push = |a: u32, b: u32, c: u32| {
+            if a == b || b == c || a == c { return; }
+            let (b, c) = if flip { (c, b) } else { (b, c) };
+            mesh.indices.push(Triangle { i0: base + a, i1: base + b, i2: base + c });
+        };
+        match mode {
+            4 => for t in indices.chunks_exact(3) { push(t[0], t[1], t[2]) },
+            // Tira: los impares invierten el orden para conservar la orientación
+            5 => for (k, t) in indices.windows(3).enumerate() {
+                if k % 2 == 0 { push(t[0], t[1], t[2]) } else { push(t[1], t[0], t[2]) }
+            },
+            _ => for t in indices.windows(2).skip(1) { push(indices[0], t[0], t[1]) },
+        }
+        Ok(uvs.is_some())
+    }
+
+    /// Accessor de floats con `comps` componentes, aplanado
+    fn accessor(&self, i: usize, comps: usize) -> Result<Vec<f32>, String> {
+        Ok(self.accessor_raw(i, comps)?.into_iter().map(|v| v as f32).collect())
+    }
+
+    /// Lee el accessor `i`, que debe tener `comps` componentes por elemento.
+    /// Los enteros `normalized` se llevan a 0..1 (o -1..1).
+    fn accessor_raw(&self, i: usize, comps: usize) -> Result<Vec<f64>, String> {
+        let ctx = |msg: String| format!("accessors[{i}]: {msg}");
+        let acc = self.item("accessors", i)?;
+        if acc.get("sparse").is_some() { return Err(ctx("accessors sparse no soportados".into())); }
+
+        let count = acc.get("count").and_then(Json::as_usize).ok_or_else(|| ctx("falta count".into()))?;
+        let kind = acc.get("type").and_then(Json::as_str).unwrap_or("");
+        let n = match kind { "SCALAR" => 1, "VEC2" => 2, "VEC3" => 3, "VEC4" => 4, _ => 0 };
+        if n != comps { return Err(ctx(format!("tipo '{kind}', se esperaban {comps} componentes"))); }
+        let ctype = acc.get("componentType").and_then(Json::as_usize).unwrap_or(0);
+        let size = match ctype { 5120 | 5121 => 1, 5122 | 5123 => 2, 5125 | 5126 => 4, _ => 0 };
+        if size == 0 { return Err(ctx(format!("componentType {ctype} no soportado"))); }
+        let normalized = acc.get("normalized").and_then(Json::as_bool).unwrap_or(false);
+
+        // Sin bufferView el accessor vale cero
+        let Some(view) = acc.get("bufferView") else { return Ok(vec![0.0; count * n]) };
+        let view_idx = view.as_usize().ok_or_else(|| ctx("bufferView inválido".into()))?;
+        let view = self.item("bufferViews", view_idx).map_err(ctx)?;
+        let vctx = |msg: String| format!("bufferViews[{view_idx}]: {msg}");
+        let buffer = view.get("buffer").and_then(Json::as_usize)
+            .and_then(|b| self.buffers.get(b)).ok_or_else(|| vctx("buffer inválido".into()))?;
+        let view_off = view.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        let view_len = view.get("byteLength").and_then(Json::as_usize).ok_or_else(|| vctx("falta byteLength".into()))?;
+        let bytes = buffer.get(view_off..view_off + view_len).ok_or_else(|| vctx("se sale del buffer".into()))?;
+
+        let elem = size * n;
+        let stride = view.get("byteStride").and_then(Json::as_usize).unwrap_or(elem);
+        let offset = acc.get("byteOffset").and_then(Json::as_usize).unwrap_or(0);
+        if stride < elem { return Err(vctx(format!("byteStride {stride} menor que el elemento ({elem})"))); }
+        if count > 0 && offset + stride * (count - 1) + elem > bytes.len() {
+            return Err(ctx(format!("{count} elementos no entran en bufferViews[{view_idx}]")));
+        }
+
+        let mut out = Vec::with_capacity(count * n);
+        for e in 0..count {
+            for c in 0..n {
+                let at = offset + e * stride + c * size;
+                let b = &bytes[at..at + size];
+                let v = match ctype {
+                    5120 => b[0] as i8 as f64,
+                    5121 => b[0] as f64,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                out.push(match (normalized, ctype) {
+                    (true, 5120) => (v / 127.0).max(-1.0),
+                    (true, 5121) => v / 255.0,
+                    (true, 5122) => (v / 32767.0).max(-1.0),
+                    (true, 5123) => v / 65535.0,
+                    _ => v,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    fn material(&self, m: &Json) -> Result<Material, String> {
+        let pbr = m.get("pbrMetallicRoughness").unwrap_or(&Json::Null);
+        let [r, g, b, _] = floats(pbr, "baseColorFactor", [1.0; 4])?;
+        let diffuse_map = match pbr.get("baseColorTexture").and_then(|t| t.get("index")).and_then(Json::as_usize) {
+            Some(t) => {
+                let source = self.item("textures", t)?.get("source").and_then(Json::as_usize);
+                // Imágenes embebidas (bufferView o data URI) no tienen ruta
+                source.and_then(|s| self.item("images", s).ok()?.get("uri")?.as_str())
+                    .filter(|uri| !uri.starts_with("data:"))
+                    .map(|uri| uri_path(uri, self.dir))
+            }
+            None => None,
+        };
+        Ok(Material {
+            name: m.get("name").and_then(Json::as_str).unwrap_or("").to_string(),
+            diffuse: Vec3::new(r, g, b),
+            diffuse_map,
+            ..Default::default()
+        })
+    }
+}
+
+/// Transformación local de un nodo: `matrix` (por columnas) o T·R·S
+fn node_matrix(node: &Json) -> Result<Mat4, String> {
+    if node.get("matrix").is_some() {
+        let a = floats(node, "matrix", [0.0; 16])?;
+        let mut m = Mat4::identity();
+        for (c, col) in a.chunks_exact(4).enumerate() {
+            for (r, &v) in col.iter().enumerate() { m.m[r][c] = v; }
+        }
+        return Ok(m);
+    }
+    let [tx, ty, tz] = floats(node, "translation", [0.0; 3])?;
+    let [x, y, z, w] = floats(node, "rotation", [0.0, 0.0, 0.0, 1.0])?;
+    let [sx, sy, sz] = floats(node, "scale", [1.0; 3])?;
+    let rot = Mat4 { m: [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w),       2.0 * (x * z + y * w),       0.0],
+        [2.0 * (x * y + z * w),       1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w),       0.0],
+        [2.0 * (x * z - y * w),       2.0 * (y * z + x * w),       1.0 - 2.0 * (x * x + y * y), 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]};
+    Ok(translate(Vec3::new(tx, ty, tz)) * rot * scale(Vec3::new(sx, sy, sz)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    /// Un triángulo en z = 0 con normales +z y UV, en un buffer de 84 bytes
+    fn triangle_bin() -> Vec<u8> {
+        let mut bin = Vec::new();
+        for f in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] { bin.extend(f.to_le_bytes()); }
+        for f in [0.0f32, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0] { bin.extend(f.to_le_bytes()); }
+        for f in [0.0f32, 1.0, 1.0, 1.0, 0.0, 0.0] { bin.extend(f.to_le_bytes()); }
+        for i in [0u16, 1, 2, 0] { bin.extend(i.to_le_bytes()); }
+        bin
+    }
+
+    fn triangle_json(buffer: &str, nodes: &str) -> String {
+        format!(r#"{{
+  "asset": {{"version": "2.0"}},
+  "buffers": [{buffer}],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 72, "byteLength": 24}},
+    {{"buffer": 0, "byteOffset": 96, "byteLength": 6}}
+  ],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3"}},
+    {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2"}},
+    {{"bufferView": 3, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+  ],
+  "materials": [{{"name": "roca", "pbrMetallicRoughness": {{"baseColorFactor": [0.5, 0.4, 0.3, 1]}}}}],
+  "meshes": [{{"name": "tri", "primitives": [{{"attributes": {{"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2}}, "indices": 3, "material": 0}}]}}],
+  {nodes}
+}}"#)
+    }
+
+    #[test]
+    fn glb_instances_nodes_with_transforms() {
+        let nodes = r#""scene": 0,
+  "scenes": [{"nodes": [0, 2]}],
+  "nodes": [
+    {"name": "padre", "translation": [10, 0, 0], "children": [1]},
+    {"name": "hijo", "mesh": 0, "rotation": [0, 0.7071068, 0, 0.7071068]},
+    {"mesh": 0, "matrix": [-1,0,0,0, 0,1,0,0, 0,0,1,0, 0,0,0,1]}
+  ]"#;
+        let mut json = triangle_json(r#"{"byteLength": 102}"#, nodes).into_bytes();
+        while !json.len().is_multiple_of(4) { json.push(b' '); }
+        let mut bin = triangle_bin();
+        bin.resize(104, 0);
+
+        let mut glb = Vec::new();
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(CHUNK_JSON.to_le_bytes());
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(CHUNK_BIN.to_le_bytes());
+        glb.extend(&bin);
+
+        let mesh = parse_gltf(&glb, "t.glb", Path::new("")).unwrap();
+        assert_eq!(mesh.vertices.len(), 6);
+        assert_eq!(mesh.submeshes, vec![
+            SubMesh { name: "hijo".into(), triangles: 0..1, material: Some(0) },
+            SubMesh { name: "tri".into(), triangles: 1..2, material: Some(0) },
+        ]);
+        assert_eq!(mesh.materials[0].diffuse, Vec3::new(0.5, 0.4, 0.3));
+
+        // Hijo: 90° alrededor de y y luego +10 en x del padre
+        let close = |a: Vec3, b: Vec3| (a - b).length() < 1e-5;
+        let hijo = &mesh.vertices[..3];
+        assert!(close(hijo[1].pos, Vec3::new(10.0, 0.0, -1.0)));
+        assert!(close(hijo[0].nrm, Vec3::new(1.0, 0.0, 0.0)));
+        // v invertida respecto de glTF
+        assert_eq!(hijo[2].uv, Vec2::new(0.0, 1.0));
+
+        // Espejo en x: la normal se conserva y el triángulo sigue mirando hacia ella
+        let espejo = &mesh.vertices[3..];
+        assert!(close(espejo[1].pos, Vec3::new(-1.0, 0.0, 0.0)));
+        let tri = mesh.indices[1];
+        let [a, b, c] = [tri.i0, tri.i1, tri.i2].map(|i| mesh.vertices[i as usize]);
+        assert!((b.pos - a.pos).cross(c.pos - a.pos).dot(a.nrm) > 0.0);
+        assert!(close(a.nrm, Vec3::new(0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn gltf_with_external_and_embedded_buffers() {
+        assert_eq!(base64_decode("AACAPw==").unwrap(), 1.0f32.to_le_bytes());
+        assert!(base64_decode("AA*A").is_err());
+
+        let dir = temp_dir("gltf");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tri angle.bin"), triangle_bin()).unwrap();
+        let json = triangle_json(r#"{"uri": "tri%20angle.bin", "byteLength": 102}"#, r#""nodes": [{"mesh": 0}]"#);
+        std::fs::write(dir.join("tri.gltf"), &json).unwrap();
+        let mesh = load_gltf(dir.join("tri.gltf").to_str().unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+        let mesh = mesh.unwrap();
+        assert_eq!((mesh.vertices.len(), mesh.indices.len()), (3, 1));
+        assert!((mesh.vertices[0].tangent.xyz() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+
+        // Errores con el objeto del glTF que falla
+        let missing = triangle_json(r#"{"uri": "no_existe.bin", "byteLength": 102}"#, r#""nodes": [{"mesh": 0}]"#);
+        assert!(parse_gltf(missing.as_bytes(), "m.gltf", Path::new("")).unwrap_err().starts_with("m.gltf: buffers[0]: No pude abrir"));
+        let short = triangle_json(r#"{"uri": "data:application/octet-stream;base64,AACAPw==", "byteLength": 4}"#, r#""nodes": [{"mesh": 0}]"#);
+        assert_eq!(parse_gltf(short.as_bytes(), "m.gltf", Path::new("")).unwrap_err(), "m.gltf: meshes[0].primitives[0]: bufferViews[0]: se sale del buffer");
+        let cycle = triangle_json(r#"{"uri": "data:application/octet-stream;base64,", "byteLength": 0}"#, r#""scenes": [{"nodes": [0]}], "nodes": [{"children": [1]}, {"children": [0]}]"#);
+        assert!(parse_gltf(cycle.as_bytes(), "m.gltf", Path::new("")).unwrap_err().contains("ciclo"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use crate::math::{self, Mat4, Vec3, rotation_y};
+use crate::math::mat::rotation_x;
+use crate::renderer::{Framebuffer, Uniforms};
+use crate::renderer::pipeline::PipelineState;
+use crate::renderer::queue::RenderQueue;
+use crate::renderer::raster::DrawStats;
+use crate::renderer::shadow::{ShadowMap, ShadowSettings};
+use crate::shaders::{ShaderKind, ShaderParams};
+use super::{Camera, Mesh, Orbit};
+use super::nbody::NBody;
+use super::showcase::{BACKGROUND, PlanetMeshes, frame_uniforms};
+
+/// Radio de una esfera que contiene cualquier malla de planeta: la esfera unidad estirada
+/// por el shader de los anillos
+pub const MESH_RADIUS: f32 = 1.6;
+
+/// Escena completa: grafo de planetas, cámara inicial y luz
+#[derive(Clone, Debug)]
+pub struct Scene {
+    /// Cámara inicial; `aspect` lo ajusta quien dibuja
+    pub camera: Camera,
+    /// Dirección (normalizada) hacia la luz
+    pub light_dir: Vec3,
+    /// Radio de una esfera centrada en el origen que contiene la escena (shadow map, órbita
+    /// de la cámara). Se fija al cargar: con gravedad no sigue a los cuerpos.
+    pub radius: f32,
+    pub shadows: bool,
+    pub root: Node,
+    /// Modo de gravedad: el cuerpo `i` de la simulación mueve a `root.children[i]`
+    pub gravity: Option<NBody>,
+}
+
+impl Scene {
+    /// Avanza la gravedad (si la hay) hasta `time` y pone cada cuerpo en su posición.
+    /// Hay que llamarla antes de dibujar el frame de ese tiempo.
+    pub fn advance(&mut self, time: f32) {
+        let Some(sim) = &mut self.gravity else { return };
+        sim.advance_to(time);
+        for (node, body) in self.root.children.iter_mut().zip(sim.bodies()) {
+            let m = &mut node.transform.m;
+            (m[0][3], m[1][3], m[2][3]) = (body.position.x, body.position.y, body.position.z);
+        }
+    }
+
+    /// Uniforms del frame: los de `frame_uniforms` con la luz de la escena
+    pub fn uniforms(&self, cam: &Camera, time: f32) -> Uniforms {
+        Uniforms { light_dir: self.light_dir, ..frame_uniforms(cam, time) }
+    }
+
+    /// Limpia el framebuffer y dibuja el frame completo. `shadow_map` guarda el shadow map
+    /// entre frames para no reservarlo de nuevo (empieza en `None`).
+    pub fn render(&self, fb: &mut Framebuffer, meshes: &PlanetMeshes<Mesh>, uniforms: &Uniforms,
+                  shadow_map: &mut Option<Arc<ShadowMap>>) -> DrawStats {
+        fb.clear_color(BACKGROUND);
+        fb.clear_depth();
+        let vp = math::viewport(0.0, 0.0, fb.width as f32, fb.height as f32, 1.0);
+
+        let mut queue = RenderQueue::new();
+        if self.shadows {
+            queue.enable_shadows(ShadowSettings { light_dir: uniforms.light_dir, radius: self.radius, ..Default::default() }, shadow_map);
+        }
+        self.root.submit(&mut queue, meshes, uniforms);
+        queue.flush(fb, vp)
+    }
+}
+
+/// Nodo del grafo de escena. Su transformación respecto del padre es `transform` desplazada
+/// por la órbita y seguida de la inclinación del eje y el giro propio; la heredan todos sus
+/// hijos, así una luna hija de un planeta lo sigue en su órbita.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    /// Transformación fija respecto del padre
+    pub transform: Mat4,
+    /// Órbita kepleriana que se suma a la posición de `transform`. Solo traslada: la
+    /// orientación del nodo (y de sus hijos) no gira con la órbita.
+    pub orbit: Option<Orbit>,
+    /// Inclinación del eje de giro (rad), alrededor del eje x. No la heredan los hijos.
+    pub tilt: f32,
+    /// Giro propio (rad/s) alrededor del eje (inclinado) del nodo. Como la órbita no gira
+    /// la orientación, es la rotación sidérea.
+    pub spin: f32,
+    /// Qué dibuja: la malla del planeta `shader.kind()` con este shader
+    pub shader: Option<ShaderParams>,
+    pub state: PipelineState,
+    pub children: Vec<Node>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            transform: Mat4::identity(),
+            orbit: None,
+            tilt: 0.0,
+            spin: 0.0,
+            shader: None,
+            state: PipelineState::default(),
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Node {
+    /// Nodo vacío que solo agrupa y posiciona a sus hijos
+    pub fn group(name: &str, transform: Mat4, children: Vec<Node>) -> Self {
+        Self { name: name.into(), transform, children, ..Default::default() }
+    }
+
+    /// Transformación respecto del padre en el tiempo `time`
+    pub fn local(&self, time: f32) -> Mat4 {
+        let mut m = self.transform;
+        if let Some(orbit) = &self.orbit {
+            let p = orbit.position(time);
+            m.m[0][3] += p.x;
+            m.m[1][3] += p.y;
+            m.m[2][3] += p.z;
+        }
+        m * rotation_x(self.tilt) * rotation_y(time * self.spin)
+    }
+
+    /// Radio de una esfera centrada en el origen del padre que contiene al subárbol en
+    /// cualquier tiempo, si cada malla cabe en una esfera de radio `mesh_radius`
+    pub fn bounding_radius(&self, mesh_radius: f32) -> f32 {
+        let m = self.transform.m;
+        let offset = Vec3::new(m[0][3], m[1][3], m[2][3]).length() + self.orbit.map_or(0.0, |o| o.apoapsis());
+        // Escala máxima de la parte lineal (exacta si no hay cizalla: rotación por escala)
+        let stretch = (0..3).map(|j| Vec3::new(m[0][j], m[1][j], m[2][j]).length()).fold(0.0, f32::max);
+        let own = if self.shader.is_some() { mesh_radius } else { 0.0 };
+        let children = self.children.iter().map(|c| c.bounding_radius(mesh_radius)).fold(own, f32::max);
+        offset + stretch * children
+    }
+
+    /// Recorre el subárbol en preorden con la transformación de mundo de cada nodo
+    pub fn visit<'a>(&'a self, parent: Mat4, time: f32, f: &mut impl FnMut(&'a Node, Mat4)) {
+        let world = parent * self.local(time);
+        f(self, world);
+        for child in &self.children {
+            child.visit(world, time, f);
+        }
+    }
+
+    /// Primer nodo (en preorden) que cumple `pred`, con su transformación de mundo
+    pub fn find(&self, time: f32, pred: impl Fn(&Node) -> bool) -> Option<(&Node, Mat4)> {
+        let mut found = None;
+        self.visit(Mat4::identity(), time, &mut |node, world| {
+            if found.is_none() && pred(node) { found = Some((node, world)); }
+        });
+        found
+    }
+
+    /// Shader y transformación de mundo del primer nodo que dibuja el planeta `kind`
+    pub fn find_shader(&self, kind: ShaderKind, time: f32) -> Option<(ShaderParams, Mat4)> {
+        let (node, world) = self.find(time, |n| n.shader.is_some_and(|s| s.kind() == kind))?;
+        Some((node.shader?, world))
+    }
+
+    /// Encola un draw por cada nodo con shader, con `model` = su transformación de mundo
+    pub fn submit<'a>(&self, queue: &mut RenderQueue<'a>, meshes: &'a PlanetMeshes<Mesh>, uniforms: &Uniforms) {
+        self.visit(Mat4::identity(), uniforms.time, &mut |node, world| {
+            let Some(shader) = node.shader else { return };
+            let mut u = uniforms.clone();
+            u.model = world;
+            queue.submit(meshes.get(shader.kind()), shader.build(), &u, &node.state);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Vec3, Vec4};
+    use crate::math::mat::translate;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn children_inherit_orbit_and_transform() {
+        let moon = Node { name: "moon".into(), transform: translate(Vec3::new(1.0, 0.0, 0.0)), shader: Some(ShaderParams::new(ShaderKind::Moon)), ..Default::default() };
+        let orbit = Orbit::through(Vec3::new(4.0, 0.0, 0.0), FRAC_PI_2);
+        let planet = Node { orbit: Some(orbit), ..Node::group("planet", Mat4::identity(), vec![moon]) };
+        let root = Node::group("root", Mat4::identity(), vec![planet]);
+
+        let center = |m: Mat4| (m * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+        // Un cuarto de vuelta en 1 s: el planeta pasa de +x a -z y la luna lo acompaña
+        // (sigue a +x del planeta, porque la órbita no gira la orientación)
+        let (_, planet_at) = root.find(1.0, |n| n.name == "planet").unwrap();
+        assert!((center(planet_at) - Vec3::new(0.0, 0.0, -4.0)).length() < 1e-5);
+        let (shader, moon_at) = root.find_shader(ShaderKind::Moon, 1.0).unwrap();
+        assert_eq!(shader.kind(), ShaderKind::Moon);
+        assert!((center(moon_at) - Vec3::new(1.0, 0.0, -4.0)).length() < 1e-5);
+        assert!(root.find_shader(ShaderKind::Gas, 1.0).is_none());
+
+        // El giro propio sí gira la orientación
+        let x_axis = |m: Mat4| (m * Vec4::new(1.0, 0.0, 0.0, 0.0)).xyz();
+        let spinning = Node { spin: FRAC_PI_2, ..Default::default() };
+        assert!((x_axis(spinning.local(1.0)) - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-5);
+        // ...y la inclinación tumba el eje de giro
+        let y_axis = |m: Mat4| (m * Vec4::new(0.0, 1.0, 0.0, 0.0)).xyz();
+        let tilted = Node { tilt: FRAC_PI_2, ..spinning };
+        assert!((y_axis(tilted.local(0.3)) - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-5);
+    }
+}
@@ -1,7 +1,23 @@
 pub mod model;
+pub mod obj;
+pub mod gltf;
+pub mod ply;
 pub mod camera;
 pub mod input;
+pub mod showcase;
+pub mod graph;
+pub mod orbit;
+pub mod nbody;
+pub mod file;
 
-pub use model::{Mesh, load_obj};
+pub use model::{Mesh, MeshSource, Material, SubMesh, load_mesh, save_mesh};
+pub use obj::{load_obj, save_obj};
+pub use gltf::load_gltf;
+pub use ply::{PlyFormat, load_ply, save_ply};
 pub use camera::Camera;
 pub use input::{Input, Action};
+pub use showcase::{Showcase, PlanetMeshes};
+pub use graph::{Node, Scene};
+pub use orbit::Orbit;
+pub use nbody::{Body, GravitySettings, NBody};
+pub use file::{SceneWatcher, load_scene, parse_scene};
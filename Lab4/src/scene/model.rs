@@ -1,11 +1,20 @@
-use std::{fs::File, io::{BufRead, BufReader}};
-use crate::math::{Vec3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::math::{Vec2, Vec3, Vec4};
+use super::{gltf::load_gltf, obj::{load_obj, save_obj}, ply::{PlyFormat, load_ply, save_ply}};
 
-/// Un vertice con posición y normal 
+/// Un vertice con posición, normal, UV y tangente
 #[derive(Copy, Clone, Debug, Default)]
 pub struct Vertex {
     pub pos: Vec3,
     pub nrm: Vec3,
+    pub uv: Vec2,
+    /// Dirección de +u sobre la superficie; `w = ±1` da el sentido de la bitangente (`nrm × t · w`)
+    pub tangent: Vec4,
 }
 
 /// Triángulo indexado
@@ -16,16 +25,58 @@ pub struct Triangle {
     pub i2: u32,
 }
 
+/// Material de un `.mtl` (solo lo básico)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// `Kd`
+    pub diffuse: Vec3,
+    /// `Ks`
+    pub specular: Vec3,
+    /// `Ns`, exponente especular
+    pub shininess: f32,
+    /// `map_Kd`, ya resuelta respecto de la carpeta del `.mtl`
+    pub diffuse_map: Option<PathBuf>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self { name: String::new(), diffuse: Vec3::new(1.0, 1.0, 1.0), specular: Vec3::ZERO, shininess: 0.0, diffuse_map: None }
+    }
+}
+
+/// Parte de una malla: triángulos consecutivos de un mismo `o`/`g` y material
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubMesh {
+    /// Nombre del grupo (`g`), o del objeto (`o`) si no hay grupo
+    pub name: String,
+    pub triangles: Range<usize>,
+    /// Índice en `Mesh::materials`
+    pub material: Option<usize>,
+}
+
 /// Malla 
 #[derive(Clone, Debug, Default)]
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<Triangle>,
+    /// Partes en orden, cubriendo todos los triángulos. Vacío = una sola pieza sin material.
+    pub submeshes: Vec<SubMesh>,
+    pub materials: Vec<Material>,
+    /// Color por vértice (RGB en 0..1), paralelo a `vertices`. Vacío = sin color.
+    pub colors: Vec<Vec3>,
 }
 
 impl Mesh {
     pub fn is_empty(&self) -> bool { self.vertices.is_empty() || self.indices.is_empty() }
 
+    /// Triángulos de una parte
+    pub fn triangles(&self, sub: &SubMesh) -> &[Triangle] { &self.indices[sub.triangles.clone()] }
+
+    pub fn submesh_by_name(&self, name: &str) -> Option<&SubMesh> {
+        self.submeshes.iter().find(|s| s.name == name)
+    }
+
     pub fn recompute_normals(&mut self) {
         // Inicializa en cero
         for v in &mut self.vertices { v.nrm = Vec3::ZERO; }
@@ -42,126 +93,359 @@ impl Mesh {
         // Normaliza
         for v in &mut self.vertices { v.nrm = v.nrm.normalize(); }
     }
-}
 
-/// Carga un .obj **sin materiales/uvs** con formato de cara `v//vn` o `v`
-/// - Soporta: `v x y z`, `vn x y z`, `f a//na b//nb c//nc` (triangulado)
-/// - Si no hay `vn`, recalcula normales.
-/// - Indices de .obj son 1-based (positivos). No soporta negativos.
-pub fn load_obj(path: &str) -> Result<Mesh, String> {
-    let file = File::open(path).map_err(|e| format!("No pude abrir {}: {}", path, e))?;
-    let reader = BufReader::new(file);
-
-    let mut positions: Vec<Vec3> = Vec::new();
-    let mut normals:   Vec<Vec3> = Vec::new();
-    let mut vertices: Vec<Vertex> = Vec::new();
-    let mut indices:  Vec<Triangle> = Vec::new();
-
-    use std::collections::HashMap;
-    #[derive(Hash, Eq, PartialEq, Copy, Clone, Debug)]
-    struct Key { v: u32, n: i32 } 
-    let mut dedup: HashMap<Key, u32> = HashMap::new();
-
-    for line in reader.lines() {
-        let line = line.map_err(|e| format!("Error leyendo {}: {}", path, e))?;
-        let s = line.trim();
-        if s.is_empty() || s.starts_with('#') { continue; }
-
-        if s.starts_with("v ") {
-            // v x y z
-            let mut it = s.split_whitespace();
-            it.next(); // "v"
-            let x: f32 = it.next().ok_or("v incompleto")?.parse().map_err(|_|"v.x inválido")?;
-            let y: f32 = it.next().ok_or("v incompleto")?.parse().map_err(|_|"v.y inválido")?;
-            let z: f32 = it.next().ok_or("v incompleto")?.parse().map_err(|_|"v.z inválido")?;
-            positions.push(Vec3::new(x,y,z));
-        } else if s.starts_with("vn ") {
-            // vn x y z
-            let mut it = s.split_whitespace();
-            it.next(); // "vn"
-            let x: f32 = it.next().ok_or("vn incompleto")?.parse().map_err(|_|"vn.x inválido")?;
-            let y: f32 = it.next().ok_or("vn incompleto")?.parse().map_err(|_|"vn.y inválido")?;
-            let z: f32 = it.next().ok_or("vn incompleto")?.parse().map_err(|_|"vn.z inválido")?;
-            normals.push(Vec3::new(x,y,z).normalize());
-        } else if s.starts_with('f') {
-            // f a//na b//nb c//nc   
-            let parts: Vec<&str> = s.split_whitespace().collect();
-            if parts.len() < 4 { return Err(format!("Cara inválida: {}", s)); }
-
-            // Convierte cada “token de vértice” a (v_idx, vn_idx|-1)
-            let mut face_idx: Vec<u32> = Vec::new(); 
-            for p in &parts[1..] {
-                let (v_i, vn_i_opt) = parse_face_token(p)?;
-                let key = Key{ v: v_i, n: vn_i_opt.unwrap_or(-1) };
-                let idx = if let Some(&found) = dedup.get(&key) {
-                    found
-                } else {
-                    // Creamos nuevo Vertex
-                    let pos = positions.get((v_i-1) as usize)
-                        .ok_or_else(|| format!("Índice v fuera de rango en {}", s))?;
-                    let nrm = if let Some(vn_i) = vn_i_opt {
-                        // vn_i es 1-based
-                        *normals.get((vn_i-1) as usize)
-                            .ok_or_else(|| format!("Índice vn fuera de rango en {}", s))?
-                    } else {
-                        Vec3::ZERO 
-                    };
-                    let new_index = vertices.len() as u32;
-                    vertices.push(Vertex{ pos: *pos, nrm });
-                    dedup.insert(key, new_index);
-                    new_index
-                };
-                face_idx.push(idx);
+    /// Tangentes a partir de las UV (promedio por vértice, ortogonalizado contra la normal).
+    /// Vértices sin triángulos con UV útiles quedan con tangente cero.
+    pub fn recompute_tangents(&mut self) {
+        let mut tan = vec![Vec3::ZERO; self.vertices.len()];
+        let mut bit = vec![Vec3::ZERO; self.vertices.len()];
+        for tri in &self.indices {
+            let idx = [tri.i0 as usize, tri.i1 as usize, tri.i2 as usize];
+            let [a, b, c] = idx.map(|i| self.vertices[i]);
+            let (e1, e2) = (b.pos - a.pos, c.pos - a.pos);
+            let (d1, d2) = (b.uv - a.uv, c.uv - a.uv);
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < 1e-12 { continue; }
+            let r = 1.0 / det;
+            let t = (e1 * d2.y - e2 * d1.y) * r;
+            let bt = (e2 * d1.x - e1 * d2.x) * r;
+            for i in idx {
+                tan[i] += t;
+                bit[i] += bt;
+            }
+        }
+        for ((v, t), b) in self.vertices.iter_mut().zip(tan).zip(bit) {
+            let t = t - v.nrm * v.nrm.dot(t);
+            if t.length() < 1e-12 { v.tangent = Vec4::ZERO; continue; }
+            let t = t.normalize();
+            let w = if v.nrm.cross(t).dot(b) < 0.0 { -1.0 } else { 1.0 };
+            v.tangent = Vec4::new(t.x, t.y, t.z, w);
+        }
+    }
+
+    /// Subdivisión adaptativa de una malla esférica centrada en el origen: hasta `passes`
+    /// veces, parte las aristas para las que `split(a, b)` (posiciones) es verdadero.
+    /// Los vértices nuevos quedan sobre la esfera (radio medio de los extremos), y los
+    /// triángulos con 1, 2 o 3 aristas partidas se dividen en 2, 3 o 4, así que no quedan
+    /// grietas. Partes, materiales y colores se conservan.
+    pub fn refine_sphere(&self, passes: u32, split: impl Fn(Vec3, Vec3) -> bool) -> Mesh {
+        let mut mesh = self.clone();
+        for _ in 0..passes {
+            let mut mids: HashMap<(u32, u32), Option<u32>> = HashMap::new();
+            let mut next = Mesh { indices: Vec::with_capacity(mesh.indices.len() * 2), ..mesh.clone() };
+            let whole = [SubMesh { name: String::new(), triangles: 0..mesh.indices.len(), material: None }];
+            let parts = if mesh.submeshes.is_empty() { &whole[..] } else { &mesh.submeshes[..] };
+
+            for (p, part) in parts.iter().enumerate() {
+                let start = next.indices.len();
+                for tri in mesh.triangles(part) {
+                    let t = [tri.i0, tri.i1, tri.i2];
+                    let m = [0, 1, 2].map(|k| {
+                        let (a, b) = (t[k], t[(k + 1) % 3]);
+                        *mids.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                            let (va, vb) = (mesh.vertices[a as usize], mesh.vertices[b as usize]);
+                            if !split(va.pos, vb.pos) { return None; }
+                            next.vertices.push(sphere_midpoint(&va, &vb));
+                            if !mesh.colors.is_empty() {
+                                next.colors.push((mesh.colors[a as usize] + mesh.colors[b as usize]) * 0.5);
+                            }
+                            Some(next.vertices.len() as u32 - 1)
+                        })
+                    });
+                    split_triangle(t, m, &mut next.indices);
+                }
+                if let Some(sub) = next.submeshes.get_mut(p) { sub.triangles = start..next.indices.len(); }
             }
+            let grew = next.indices.len() > mesh.indices.len();
+            mesh = next;
+            if !grew { break; }
+        }
+        mesh
+    }
+
+    /// Esfera UV de `segments` meridianos y `rings` paralelos.
+    /// La costura (u = 0 y u = 1) y los polos tienen vértices propios.
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Mesh {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        let row = segments + 1;
+        let mut vertices = Vec::with_capacity((row * (rings + 1)) as usize);
+        for r in 0..=rings {
+            let v = r as f32 / rings as f32;
+            for s in 0..=segments {
+                // En los polos cada triángulo tiene su vértice, con la u de su centro
+                let ds = if r == 0 || r == rings { 0.5 } else { 0.0 };
+                let uv = Vec2::new((s as f32 + ds) / segments as f32, v);
+                vertices.push(sphere_vertex(radius, dir_from_uv(uv), uv));
+            }
+        }
 
-            for i in 2..face_idx.len() {
-                indices.push(Triangle{ i0: face_idx[0], i1: face_idx[i-1], i2: face_idx[i] });
+        let mut indices = Vec::with_capacity((2 * segments * rings) as usize);
+        for r in 0..rings {
+            for s in 0..segments {
+                let a = r * row + s;
+                let (b, c, d) = (a + 1, a + row, a + row + 1);
+                // En cada polo uno de los dos triángulos del quad es degenerado
+                if r != rings - 1 { indices.push(Triangle { i0: a, i1: c, i2: d }); }
+                if r != 0 { indices.push(Triangle { i0: a, i1: d, i2: b }); }
             }
-        } else {
-            // Ignorar otras líneas
-            continue;
-        }
-    }
-
-    let mut mesh = Mesh { vertices, indices };
-
-    let had_normals = mesh.vertices.iter().any(|v| v.nrm.length() > 0.0);
-    if !had_normals {
-        mesh.recompute_normals();
-    }
-    Ok(mesh)
-}
-
-/// Parsea un token de cara:
-/// - "a//na" -> (a, Some(na))
-/// - "a"     -> (a, None)
-/// - "a/b/c" o "a/b" los rechazamos para mantener sencillo (no hay vt)
-// Acepta: "a/b/c" (v/vt/vn), "a//c" (v//vn), "a/b" (v/vt), "a" (v)
-// Ignora vt; usa vn si está.
-fn parse_face_token(tok: &str) -> Result<(u32, Option<i32>), String> {
-    let parts: Vec<&str> = tok.split('/').collect();
-    match parts.len() {
-        1 => {
-            // "a"
-            let v: u32 = parts[0].parse().map_err(|_| format!("v inválido en '{}'", tok))?;
-            Ok((v, None))
-        }
-        2 => {
-            // "a/b"  (sin vn)
-            let v: u32 = parts[0].parse().map_err(|_| format!("v inválido en '{}'", tok))?;
-            Ok((v, None))
-        }
-        3 => {
-            // "a/b/c" o "a//c"
-            let v: u32 = parts[0].parse().map_err(|_| format!("v inválido en '{}'", tok))?;
-            let vn_opt = if parts[2].is_empty() {
-                None
-            } else {
-                Some(parts[2].parse::<i32>().map_err(|_| format!("vn inválido en '{}'", tok))?)
+        }
+        Mesh { vertices, indices, ..Default::default() }
+    }
+
+    /// Icosaedro subdividido `level` veces (20·4^level triángulos)
+    pub fn icosphere(radius: f32, level: u32) -> Mesh {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut dirs: Vec<Vec3> = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ].iter().map(|&(x, y, z)| Vec3::new(x, y, z).normalize()).collect();
+        let mut tris: Vec<[u32; 3]> = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..level {
+            let mut mid: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32, dirs: &mut Vec<Vec3>| {
+                *mid.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    dirs.push((dirs[a as usize] + dirs[b as usize]).normalize());
+                    dirs.len() as u32 - 1
+                })
             };
-            Ok((v, vn_opt))
+            let mut next = Vec::with_capacity(tris.len() * 4);
+            for [a, b, c] in tris {
+                let ab = midpoint(a, b, &mut dirs);
+                let bc = midpoint(b, c, &mut dirs);
+                let ca = midpoint(c, a, &mut dirs);
+                next.extend([[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+            }
+            tris = next;
+        }
+        spherical_mesh(radius, &dirs, &tris)
+    }
+
+    /// Cubo de `divisions`×`divisions` quads por cara, proyectado a la esfera
+    pub fn cube_sphere(radius: f32, divisions: u32) -> Mesh {
+        let n = divisions.max(1);
+        let axes = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)];
+        let mut dirs = Vec::with_capacity((6 * (n + 1) * (n + 1)) as usize);
+        let mut tris = Vec::with_capacity((12 * n * n) as usize);
+        for k in 0..3 {
+            for sign in [1.0, -1.0] {
+                let (normal, a, b) = (axes[k] * sign, axes[(k + 1) % 3], axes[(k + 2) % 3]);
+                let base = dirs.len() as u32;
+                for j in 0..=n {
+                    for i in 0..=n {
+                        let (x, y) = (2.0 * i as f32 / n as f32 - 1.0, 2.0 * j as f32 / n as f32 - 1.0);
+                        dirs.push((normal + a * x + b * y).normalize());
+                    }
+                }
+                for j in 0..n {
+                    for i in 0..n {
+                        let q = base + j * (n + 1) + i;
+                        tris.push([q, q + 1, q + n + 2]);
+                        tris.push([q, q + n + 2, q + n + 1]);
+                    }
+                }
+            }
+        }
+        spherical_mesh(radius, &dirs, &tris)
+    }
+}
+
+/// Mismo mapeo que usan los shaders: u = longitud, v = latitud, ambos en [0, 1]
+fn spherical_uv(n: Vec3) -> Vec2 {
+    Vec2::new(n.z.atan2(n.x) / (2.0 * PI) + 0.5, n.y.clamp(-1.0, 1.0).asin() / PI + 0.5)
+}
+
+fn dir_from_uv(uv: Vec2) -> Vec3 {
+    let (lon, lat) = ((uv.x - 0.5) * 2.0 * PI, (uv.y - 0.5) * PI);
+    Vec3::new(lat.cos() * lon.cos(), lat.sin(), lat.cos() * lon.sin())
+}
+
+/// Vértice de la esfera en la dirección `n`. La tangente es la derivada de la posición
+/// respecto de u (hacia el este), definida también en los polos.
+fn sphere_vertex(radius: f32, n: Vec3, uv: Vec2) -> Vertex {
+    let lon = (uv.x - 0.5) * 2.0 * PI;
+    // +v va hacia el norte, que es -(n × t)
+    let tangent = Vec4::new(-lon.sin(), 0.0, lon.cos(), -1.0);
+    Vertex { pos: n * radius, nrm: n, uv, tangent }
+}
+
+/// Malla esférica a partir de direcciones unitarias: orienta los triángulos hacia afuera
+/// y duplica vértices en la costura de u y en los polos para que las UV no se estiren
+fn spherical_mesh(radius: f32, dirs: &[Vec3], tris: &[[u32; 3]]) -> Mesh {
+    let mut vertices: Vec<Vertex> = dirs.iter().map(|&n| sphere_vertex(radius, n, spherical_uv(n))).collect();
+    let mut seam: HashMap<u32, u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(tris.len());
+
+    for &[a, b, c] in tris {
+        let (pa, pb, pc) = (dirs[a as usize], dirs[b as usize], dirs[c as usize]);
+        let mut t = if (pb - pa).cross(pc - pa).dot(pa + pb + pc) < 0.0 { [a, c, b] } else { [a, b, c] };
+
+        let u = t.map(|i| vertices[i as usize].uv.x);
+        if u.iter().cloned().fold(f32::MIN, f32::max) - u.iter().cloned().fold(f32::MAX, f32::min) > 0.5 {
+            for i in &mut t {
+                if vertices[*i as usize].uv.x >= 0.5 { continue; }
+                *i = *seam.entry(*i).or_insert_with(|| {
+                    let mut v = vertices[*i as usize];
+                    v.uv.x += 1.0;
+                    vertices.push(v);
+                    vertices.len() as u32 - 1
+                });
+            }
+        }
+
+        for k in 0..3 {
+            let v = vertices[t[k] as usize];
+            if v.nrm.y.abs() < 1.0 - 1e-6 { continue; }
+            let (o1, o2) = (vertices[t[(k + 1) % 3] as usize].uv.x, vertices[t[(k + 2) % 3] as usize].uv.x);
+            vertices.push(sphere_vertex(radius, v.nrm, Vec2::new(0.5 * (o1 + o2), v.uv.y)));
+            t[k] = vertices.len() as u32 - 1;
+        }
+        indices.push(Triangle { i0: t[0], i1: t[1], i2: t[2] });
+    }
+    Mesh { vertices, indices, ..Default::default() }
+}
+
+/// Punto medio de una arista llevado a la esfera
+fn sphere_midpoint(a: &Vertex, b: &Vertex) -> Vertex {
+    let radius = 0.5 * (a.pos.length() + b.pos.length());
+    let nrm = (a.nrm + b.nrm).normalize();
+    let t = a.tangent.xyz() + b.tangent.xyz();
+    let tangent = (t - nrm * t.dot(nrm)).normalize();
+    Vertex {
+        pos: (a.pos + b.pos).normalize() * radius,
+        nrm,
+        uv: (a.uv + b.uv) * 0.5,
+        tangent: Vec4::from3(tangent, a.tangent.w),
+    }
+}
+
+/// Divide el triángulo `t` según los puntos medios `m[k]` de sus aristas `t[k]`-`t[k+1]`,
+/// conservando la orientación
+fn split_triangle(t: [u32; 3], m: [Option<u32>; 3], out: &mut Vec<Triangle>) {
+    let mut push = |i0, i1, i2| out.push(Triangle { i0, i1, i2 });
+    match m {
+        [None, None, None] => push(t[0], t[1], t[2]),
+        [Some(a), Some(b), Some(c)] => {
+            push(t[0], a, c);
+            push(a, t[1], b);
+            push(c, b, t[2]);
+            push(a, b, c);
+        }
+        _ => {
+            // Rotar para que la arista 0 esté partida y, si hay dos, también la 1
+            let k = (0..3).find(|&k| m[k].is_some() && (m.iter().flatten().count() == 1 || m[(k + 1) % 3].is_some())).unwrap_or(0);
+            let [a, b, c] = [t[k], t[(k + 1) % 3], t[(k + 2) % 3]];
+            let mab = m[k].unwrap_or(a);
+            match m[(k + 1) % 3] {
+                None => {
+                    push(a, mab, c);
+                    push(mab, b, c);
+                }
+                Some(mbc) => {
+                    push(mab, b, mbc);
+                    push(a, mab, mbc);
+                    push(a, mbc, c);
+                }
+            }
+        }
+    }
+}
+
+/// Radio de las mallas generadas: el mismo de `assets/sphere.obj`
+pub const BUILTIN_RADIUS: f32 = 0.5;
+
+/// Carga una malla eligiendo el formato por la extensión: `.obj`, `.gltf`, `.glb` o `.ply`
+pub fn load_mesh(path: &str) -> Result<Mesh, String> {
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "obj" => load_obj(path),
+        "gltf" | "glb" => load_gltf(path),
+        "ply" => load_ply(path),
+        _ => Err(format!("{path}: formato de malla desconocido (usa .obj, .gltf, .glb o .ply)")),
+    }
+}
+
+/// Guarda una malla eligiendo el formato por la extensión: `.obj` (con su `.mtl`) o `.ply` (binario)
+pub fn save_mesh(mesh: &Mesh, path: &str) -> Result<(), String> {
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "obj" => save_obj(mesh, path),
+        "ply" => save_ply(mesh, path, PlyFormat::BinaryLittleEndian),
+        _ => Err(format!("{path}: no sé exportar ese formato (usa .obj o .ply)")),
+    }
+}
+
+/// Crea `path` y escribe en él con `f` (con buffer)
+pub(crate) fn write_file(path: &Path, f: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> Result<(), String> {
+    let err = |e: io::Error| format!("No pude escribir {}: {}", path.display(), e);
+    let mut w = BufWriter::new(File::create(path).map_err(err)?);
+    f(&mut w).and_then(|_| w.flush()).map_err(err)
+}
+
+/// De dónde sale una malla: un archivo o uno de los generadores
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshSource {
+    /// `.obj`, `.gltf`, `.glb` o `.ply`
+    File(String),
+    UvSphere { segments: u32, rings: u32 },
+    Icosphere(u32),
+    CubeSphere(u32),
+}
+
+impl Default for MeshSource {
+    fn default() -> Self { MeshSource::Icosphere(3) }
+}
+
+impl MeshSource {
+    /// `uv[:SEGxANILLOS]`, `ico[:NIVEL]`, `cube[:DIVISIONES]` o la ruta de un archivo de malla
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (kind, arg) = match s.split_once(':') {
+            Some((k, a)) => (k, Some(a)),
+            None => (s, None),
+        };
+        let count = |a: &str, max: u32| {
+            a.parse::<u32>().ok().filter(|&n| n <= max)
+                .ok_or_else(|| format!("malla '{s}': '{a}' no es un entero entre 0 y {max}"))
+        };
+        match (kind, arg) {
+            ("uv", None) => Ok(MeshSource::UvSphere { segments: 48, rings: 24 }),
+            ("uv", Some(a)) => {
+                let (seg, rings) = a.split_once('x')
+                    .ok_or_else(|| format!("malla '{s}': usa uv:SEGMENTOSxANILLOS, p. ej. uv:64x32"))?;
+                Ok(MeshSource::UvSphere { segments: count(seg, 4096)?.max(3), rings: count(rings, 4096)?.max(2) })
+            }
+            ("ico", None) => Ok(MeshSource::Icosphere(3)),
+            ("ico", Some(a)) => Ok(MeshSource::Icosphere(count(a, 7)?)),
+            ("cube", None) => Ok(MeshSource::CubeSphere(12)),
+            ("cube", Some(a)) => Ok(MeshSource::CubeSphere(count(a, 1024)?.max(1))),
+            _ => Ok(MeshSource::File(s.to_string())),
+        }
+    }
+
+    pub fn load(&self) -> Result<Mesh, String> {
+        Ok(match *self {
+            MeshSource::File(ref path) => load_mesh(path)?,
+            MeshSource::UvSphere { segments, rings } => Mesh::uv_sphere(BUILTIN_RADIUS, segments, rings),
+            MeshSource::Icosphere(level) => Mesh::icosphere(BUILTIN_RADIUS, level),
+            MeshSource::CubeSphere(n) => Mesh::cube_sphere(BUILTIN_RADIUS, n),
+        })
+    }
+}
+
+impl std::fmt::Display for MeshSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshSource::File(path) => write!(f, "{path}"),
+            MeshSource::UvSphere { segments, rings } => write!(f, "uv:{segments}x{rings}"),
+            MeshSource::Icosphere(level) => write!(f, "ico:{level}"),
+            MeshSource::CubeSphere(n) => write!(f, "cube:{n}"),
         }
-        _ => Err(format!("Token de cara no soportado: '{}'", tok)),
     }
 }
 
@@ -169,10 +453,71 @@ fn parse_face_token(tok: &str) -> Result<(u32, Option<i32>), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    /// Normales, UV y tangentes coherentes, triángulos hacia afuera y superficie completa
+    fn check_sphere(mesh: &Mesh, radius: f32) {
+        let mut area = 0.0;
+        for v in &mesh.vertices {
+            assert!((v.nrm.length() - 1.0).abs() < 1e-4);
+            assert!((v.pos - v.nrm * radius).length() < 1e-4);
+            let t = v.tangent.xyz();
+            assert!((t.length() - 1.0).abs() < 1e-4 && t.dot(v.nrm).abs() < 1e-4);
+        }
+        for tri in &mesh.indices {
+            let [a, b, c] = [tri.i0, tri.i1, tri.i2].map(|i| mesh.vertices[i as usize]);
+            let face = (b.pos - a.pos).cross(c.pos - a.pos);
+            assert!(face.dot(a.pos + b.pos + c.pos) > 0.0, "triángulo hacia adentro");
+            area += 0.5 * face.length();
+
+            // Sin costura: +u de las UV coincide con la tangente
+            let (e1, e2) = (b.pos - a.pos, c.pos - a.pos);
+            let (d1, d2) = (b.uv - a.uv, c.uv - a.uv);
+            let det = d1.x * d2.y - d2.x * d1.y;
+            if det.abs() < 1e-9 { continue; }
+            let du = (e1 * d2.y - e2 * d1.y) * (1.0 / det);
+            assert!(du.normalize().dot(a.tangent.xyz()) > 0.5, "UV o tangente incoherentes");
+        }
+        let sphere = 4.0 * PI * radius * radius;
+        assert!(area > 0.95 * sphere && area <= sphere, "área {area} vs {sphere}");
+    }
+
     #[test]
-    fn parse_faces_basic() {
-        assert_eq!(parse_face_token("3//7").unwrap(), (3, Some(7)));
-        assert_eq!(parse_face_token("12").unwrap(), (12, None));
-        assert!(parse_face_token("1/2/3").is_err());
+    fn generated_spheres_are_consistent() {
+        let uv = Mesh::uv_sphere(1.0, 16, 8);
+        assert_eq!(uv.indices.len(), 2 * 16 * 8 - 2 * 16);
+        check_sphere(&uv, 1.0);
+
+        let ico = Mesh::icosphere(0.5, 3);
+        assert_eq!(ico.indices.len(), 20 * 4usize.pow(3));
+        check_sphere(&ico, 0.5);
+
+        let cube = Mesh::cube_sphere(2.0, 6);
+        assert_eq!(cube.indices.len(), 6 * 6 * 6 * 2);
+        check_sphere(&cube, 2.0);
+
+        assert_eq!(MeshSource::parse("uv:20x10").unwrap(), MeshSource::UvSphere { segments: 20, rings: 10 });
+        assert_eq!(MeshSource::parse("assets/sphere.obj").unwrap(), MeshSource::File("assets/sphere.obj".into()));
+        assert!(MeshSource::parse("uv:20").is_err());
+        assert!(load_mesh("modelo.fbx").unwrap_err().contains("formato de malla desconocido"));
+    }
+
+    #[test]
+    fn refine_sphere_splits_only_marked_edges_without_cracks() {
+        let base = Mesh::icosphere(1.0, 1);
+        let refined = base.refine_sphere(2, |a, b| a.x > 0.2 && b.x > 0.2);
+        assert!(refined.indices.len() > base.indices.len());
+        check_sphere(&refined, 1.0);
+
+        // Cada arista (por posición, por la costura) la comparten exactamente dos triángulos
+        let key = |p: Vec3| [p.x, p.y, p.z].map(f32::to_bits);
+        let mut edges: HashMap<_, i32> = HashMap::new();
+        for tri in &refined.indices {
+            let t = [tri.i0, tri.i1, tri.i2].map(|i| key(refined.vertices[i as usize].pos));
+            for k in 0..3 {
+                *edges.entry((t[k].min(t[(k + 1) % 3]), t[k].max(t[(k + 1) % 3]))).or_default() += 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 2), "grietas en la malla refinada");
+
+        assert_eq!(base.refine_sphere(3, |_, _| false).indices.len(), base.indices.len());
     }
 }
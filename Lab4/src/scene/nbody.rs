@@ -0,0 +1,192 @@
+//! Modo de gravedad: cuerpos con masa y velocidad que se atraen entre sí, integrados con
+//! velocity Verlet a paso fijo. El estado en un tiempo dado solo depende de ese tiempo (no
+//! de los frames que llevaron hasta él), así la ventana, `render` y los tests ven lo mismo.
+
+use crate::math::Vec3;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Body {
+    pub mass: f32,
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GravitySettings {
+    /// Constante de gravitación
+    pub g: f32,
+    /// Paso fijo de la integración (s)
+    pub dt: f32,
+    /// Suaviza la fuerza a distancias menores que esta (encuentros cercanos)
+    pub softening: f32,
+}
+
+impl Default for GravitySettings {
+    fn default() -> Self {
+        Self { g: 1.0, dt: 0.005, softening: 0.05 }
+    }
+}
+
+/// Simulación de `n` cuerpos con diagnóstico de la deriva de energía
+#[derive(Clone, Debug)]
+pub struct NBody {
+    settings: GravitySettings,
+    initial: Vec<Body>,
+    bodies: Vec<Body>,
+    accel: Vec<Vec3>,
+    steps: u64,
+    initial_energy: f64,
+    max_drift: f64,
+}
+
+impl NBody {
+    pub fn new(settings: GravitySettings, bodies: Vec<Body>) -> Self {
+        let mut sim = Self {
+            settings,
+            initial: bodies.clone(),
+            bodies,
+            accel: Vec::new(),
+            steps: 0,
+            initial_energy: 0.0,
+            max_drift: 0.0,
+        };
+        sim.reset();
+        sim
+    }
+
+    pub fn settings(&self) -> GravitySettings { self.settings }
+    pub fn bodies(&self) -> &[Body] { &self.bodies }
+    pub fn steps(&self) -> u64 { self.steps }
+
+    /// Tiempo simulado (s)
+    pub fn time(&self) -> f64 {
+        self.steps as f64 * self.settings.dt as f64
+    }
+
+    /// Vuelve al estado inicial
+    pub fn reset(&mut self) {
+        self.bodies.clone_from(&self.initial);
+        self.accel = accelerations(&self.bodies, &self.settings);
+        self.steps = 0;
+        self.initial_energy = self.energy();
+        self.max_drift = 0.0;
+    }
+
+    /// Un paso de velocity Verlet
+    pub fn step(&mut self) {
+        let dt = self.settings.dt;
+        for (b, a) in self.bodies.iter_mut().zip(&self.accel) {
+            b.velocity += *a * (0.5 * dt);
+            b.position += b.velocity * dt;
+        }
+        self.accel = accelerations(&self.bodies, &self.settings);
+        for (b, a) in self.bodies.iter_mut().zip(&self.accel) {
+            b.velocity += *a * (0.5 * dt);
+        }
+        self.steps += 1;
+        self.max_drift = self.max_drift.max(self.energy_drift().abs());
+    }
+
+    /// Avanza hasta el último paso que no pasa de `time`. Si `time` quedó atrás vuelve a
+    /// empezar desde el estado inicial.
+    pub fn advance_to(&mut self, time: f32) {
+        // El margen evita perder un paso cuando `dt` no es exacto en f32 (0.1 = 0.10000000149)
+        let target = (time.max(0.0) as f64 / self.settings.dt as f64 + 1e-6).floor() as u64;
+        if target < self.steps { self.reset(); }
+        while self.steps < target {
+            self.step();
+        }
+    }
+
+    /// Energía cinética más potencial (con el mismo suavizado que la fuerza)
+    pub fn energy(&self) -> f64 {
+        let GravitySettings { g, softening, .. } = self.settings;
+        let mut e = 0.0;
+        for (i, a) in self.bodies.iter().enumerate() {
+            e += 0.5 * a.mass as f64 * a.velocity.dot(a.velocity) as f64;
+            for b in &self.bodies[i + 1..] {
+                let d = b.position - a.position;
+                let r = (d.dot(d) as f64 + (softening * softening) as f64).sqrt();
+                if r > 0.0 { e -= (g * a.mass * b.mass) as f64 / r; }
+            }
+        }
+        e
+    }
+
+    /// Cambio relativo de la energía desde el estado inicial (absoluto si era 0)
+    pub fn energy_drift(&self) -> f64 {
+        let delta = self.energy() - self.initial_energy;
+        if self.initial_energy != 0.0 { delta / self.initial_energy.abs() } else { delta }
+    }
+
+    /// Mayor `energy_drift` (en valor absoluto) de todos los pasos dados
+    pub fn max_energy_drift(&self) -> f64 {
+        self.max_drift
+    }
+}
+
+fn accelerations(bodies: &[Body], settings: &GravitySettings) -> Vec<Vec3> {
+    let eps2 = settings.softening * settings.softening;
+    let mut accel = vec![Vec3::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in i + 1..bodies.len() {
+            let d = bodies[j].position - bodies[i].position;
+            let r2 = d.dot(d) + eps2;
+            if r2 == 0.0 { continue; }
+            let f = d * (settings.g / (r2 * r2.sqrt()));
+            accel[i] += f * bodies[j].mass;
+            accel[j] -= f * bodies[i].mass;
+        }
+    }
+    accel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    /// Estrella en el origen y planeta en órbita circular de radio 1 y período 2π
+    fn star_and_planet(dt: f32) -> NBody {
+        NBody::new(GravitySettings { g: 1.0, dt, softening: 0.0 }, vec![
+            Body { mass: 1.0, ..Default::default() },
+            Body { mass: 1e-6, position: Vec3::new(1.0, 0.0, 0.0), velocity: Vec3::new(0.0, 0.0, -1.0) },
+        ])
+    }
+
+    #[test]
+    fn circular_orbit_keeps_its_energy() {
+        let mut sim = star_and_planet(0.002);
+        sim.advance_to(TAU as f32);
+        let planet = sim.bodies()[1];
+        assert!((planet.position - Vec3::new(1.0, 0.0, 0.0)).length() < 0.01, "{:?}", planet.position);
+        // Verlet es simpléctico: la energía oscila sin crecer vuelta a vuelta
+        sim.advance_to(10.0 * TAU as f32);
+        assert!(sim.max_energy_drift() < 1e-4, "{}", sim.max_energy_drift());
+        assert!(sim.energy_drift().abs() <= sim.max_energy_drift());
+    }
+
+    #[test]
+    fn stepping_is_deterministic() {
+        let three = || NBody::new(GravitySettings::default(), vec![
+            Body { mass: 1.0, position: Vec3::new(-1.0, 0.0, 0.0), velocity: Vec3::new(0.0, 0.1, 0.4) },
+            Body { mass: 0.8, position: Vec3::new(1.0, 0.2, 0.0), velocity: Vec3::new(0.0, 0.0, -0.5) },
+            Body { mass: 0.3, position: Vec3::new(0.0, 0.0, 2.0), velocity: Vec3::new(0.6, 0.0, 0.0) },
+        ]);
+        let mut direct = three();
+        direct.advance_to(3.0);
+        assert_eq!(direct.steps(), 600);
+
+        // Con frames irregulares y volviendo atrás se llega exactamente al mismo estado
+        let mut framed = three();
+        let mut t = 0.0;
+        while t < 3.0 { framed.advance_to(t); t += 0.0173; }
+        framed.advance_to(3.0);
+        let mut rewound = three();
+        rewound.advance_to(5.0);
+        rewound.advance_to(3.0);
+        assert_eq!(framed.bodies(), direct.bodies());
+        assert_eq!(rewound.bodies(), direct.bodies());
+        assert_eq!(rewound.max_energy_drift(), direct.max_energy_drift());
+    }
+}
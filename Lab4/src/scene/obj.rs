@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use crate::math::{Vec2, Vec3};
+use super::model::{Material, Mesh, SubMesh, Triangle, Vertex, write_file};
+
+/// Carga un .obj con sus materiales.
+/// - `v`, `vt`, `vn` y caras `v`, `v/vt`, `v//vn` o `v/vt/vn` (polígonos triangulados en abanico).
+/// - Índices 1-based o negativos (relativos al último elemento leído).
+/// - `o`, `g` y `usemtl` abren una nueva `SubMesh`; `mtllib` se busca junto al .obj.
+///   Como muchos exportadores, un `.mtl` ausente o un `usemtl` desconocido dejan la parte sin material.
+/// - Si falta alguna `vn`, se calculan esas normales; con `vt` se calculan tangentes.
+/// - `v x y z r g b` (extensión habitual) da color por vértice.
+///
+/// Los errores indican `archivo:línea`.
+pub fn load_obj(path: &str) -> Result<Mesh, String> {
+    let file = File::open(path).map_err(|e| format!("No pude abrir {}: {}", path, e))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    parse_obj(BufReader::new(file), path, dir)
+}
+
+/// Igual que `load_obj`, leyendo de `reader`. `name` aparece en los errores y
+/// `dir` es la carpeta contra la que se resuelven los `mtllib`.
+pub fn parse_obj<R: BufRead>(reader: R, name: &str, dir: &Path) -> Result<Mesh, String> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut texcoords: Vec<Vec2> = Vec::new();
+    let mut normals:   Vec<Vec3> = Vec::new();
+    let mut pos_colors: Vec<Option<Vec3>> = Vec::new();
+    let mut mesh = Mesh::default();
+
+    // (v, vt, vn) ya resueltos a 0-based
+    let mut dedup: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+    let mut has_normal: Vec<bool> = Vec::new();
+    let mut vertex_colors: Vec<Option<Vec3>> = Vec::new();
+    let mut has_uv = false;
+
+    // Parte en construcción
+    let (mut object, mut group) = (String::new(), String::new());
+    let mut material: Option<usize> = None;
+    let mut part_start = 0;
+
+    for (n, line) in reader.lines().enumerate() {
+        let err = |msg: String| format!("{}:{}: {}", name, n + 1, msg);
+        let line = line.map_err(|e| err(format!("error de lectura: {e}")))?;
+        let s = line.trim();
+        if s.is_empty() || s.starts_with('#') { continue; }
+
+        let (key, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let rest = rest.trim();
+        match key {
+            "v" => {
+                let [x, y, z] = floats(rest, 3, 3, "v").map_err(err)?;
+                positions.push(Vec3::new(x, y, z));
+                let extra: Vec<&str> = rest.split_whitespace().skip(3).collect();
+                pos_colors.push(if extra.len() == 3 {
+                    let [r, g, b] = floats(&extra.join(" "), 3, 3, "color de v").map_err(err)?;
+                    Some(Vec3::new(r, g, b))
+                } else {
+                    None
+                });
+            }
+            "vt" => {
+                let [u, v, _] = floats(rest, 1, 3, "vt").map_err(err)?;
+                texcoords.push(Vec2::new(u, v));
+            }
+            "vn" => {
+                let [x, y, z] = floats(rest, 3, 3, "vn").map_err(err)?;
+                normals.push(Vec3::new(x, y, z).normalize());
+            }
+            "f" => {
+                let tokens: Vec<&str> = rest.split_whitespace().collect();
+                if tokens.len() < 3 { return Err(err(format!("cara con {} vértices (mínimo 3)", tokens.len()))); }
+
+                let mut face_idx: Vec<u32> = Vec::with_capacity(tokens.len());
+                for tok in tokens {
+                    let (v, vt, vn) = parse_face_token(tok).map_err(err)?;
+                    let v = resolve_index(v, positions.len(), "v").map_err(err)?;
+                    let vt = vt.map(|i| resolve_index(i, texcoords.len(), "vt")).transpose().map_err(err)?;
+                    let vn = vn.map(|i| resolve_index(i, normals.len(), "vn")).transpose().map_err(err)?;
+
+                    let idx = *dedup.entry((v, vt, vn)).or_insert_with(|| {
+                        mesh.vertices.push(Vertex {
+                            pos: positions[v],
+                            nrm: vn.map_or(Vec3::ZERO, |i| normals[i]),
+                            uv: vt.map_or(Vec2::ZERO, |i| texcoords[i]),
+                            ..Default::default()
+                        });
+                        has_normal.push(vn.is_some());
+                        vertex_colors.push(pos_colors[v]);
+                        mesh.vertices.len() as u32 - 1
+                    });
+                    has_uv |= vt.is_some();
+                    face_idx.push(idx);
+                }
+                for i in 2..face_idx.len() {
+                    mesh.indices.push(Triangle { i0: face_idx[0], i1: face_idx[i - 1], i2: face_idx[i] });
+                }
+            }
+            "o" | "g" | "usemtl" => {
+                close_part(&mut mesh, &mut part_start, &object, &group, material);
+                match key {
+                    "o" => { object = rest.to_string(); group.clear(); }
+                    "g" => group = rest.to_string(),
+                    _ => material = mesh.materials.iter().position(|m| m.name == rest),
+                }
+            }
+            "mtllib" => {
+                if rest.is_empty() { return Err(err("mtllib sin archivo".into())); }
+                for file in rest.split_whitespace() {
+                    let path = dir.join(file);
+                    if !path.is_file() { continue; }
+                    let mtl = load_mtl(&path).map_err(|e| err(format!("mtllib: {e}")))?;
+                    mesh.materials.extend(mtl);
+                }
+            }
+            // s, l, p, curvas, etc.: no los usamos
+            _ => {}
+        }
+    }
+    close_part(&mut mesh, &mut part_start, &object, &group, material);
+
+    if has_normal.iter().any(|h| !h) {
+        let given: Vec<Vec3> = mesh.vertices.iter().map(|v| v.nrm).collect();
+        mesh.recompute_normals();
+        for ((v, &h), n) in mesh.vertices.iter_mut().zip(&has_normal).zip(given) {
+            if h { v.nrm = n; }
+        }
+    }
+    if has_uv { mesh.recompute_tangents(); }
+    if vertex_colors.iter().any(Option::is_some) {
+        mesh.colors = vertex_colors.into_iter().map(|c| c.unwrap_or(Vec3::ONE)).collect();
+    }
+    Ok(mesh)
+}
+
+/// Cierra la parte actual si tiene triángulos
+fn close_part(mesh: &mut Mesh, start: &mut usize, object: &str, group: &str, material: Option<usize>) {
+    let end = mesh.indices.len();
+    if end == *start { return; }
+    let name = if group.is_empty() { object } else { group };
+    mesh.submeshes.push(SubMesh { name: name.to_string(), triangles: *start..end, material });
+    *start = end;
+}
+
+/// Lee entre `min` y `max` (≤ 3) floats; los que faltan quedan en 0
+fn floats(rest: &str, min: usize, max: usize, what: &str) -> Result<[f32; 3], String> {
+    let mut out = [0.0; 3];
+    let mut count = 0;
+    for tok in rest.split_whitespace() {
+        // `v` puede traer w o color por vértice: se leen aparte
+        if count == max { break; }
+        out[count] = tok.parse().map_err(|_| format!("{what}: '{tok}' no es un número"))?;
+        count += 1;
+    }
+    if count < min { return Err(format!("{what} incompleto: se esperaban {min} valores")); }
+    Ok(out)
+}
+
+/// Índice de `.obj` (1-based, o negativo desde el final) a 0-based
+fn resolve_index(i: i64, count: usize, what: &str) -> Result<usize, String> {
+    let resolved = if i > 0 { i - 1 } else { count as i64 + i };
+    if i == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("índice {what} {i} fuera de rango (hay {count})"));
+    }
+    Ok(resolved as usize)
+}
+
+/// Parsea un token de cara a `(v, vt, vn)` sin resolver:
+/// "a" -> (a, None, None), "a/b" -> (a, Some(b), None),
+/// "a//c" -> (a, None, Some(c)), "a/b/c" -> (a, Some(b), Some(c))
+fn parse_face_token(tok: &str) -> Result<(i64, Option<i64>, Option<i64>), String> {
+    let parts: Vec<&str> = tok.split('/').collect();
+    if parts.len() > 3 { return Err(format!("Token de cara no soportado: '{}'", tok)); }
+    let num = |i: usize, what: &str| -> Result<Option<i64>, String> {
+        match parts.get(i) {
+            None | Some(&"") => Ok(None),
+            Some(p) => p.parse().map(Some).map_err(|_| format!("{what} inválido en '{}'", tok)),
+        }
+    };
+    let v = num(0, "v")?.ok_or_else(|| format!("v inválido en '{}'", tok))?;
+    Ok((v, num(1, "vt")?, num(2, "vn")?))
+}
+
+/// Carga los materiales de un `.mtl`
+pub fn load_mtl(path: &Path) -> Result<Vec<Material>, String> {
+    let file = File::open(path).map_err(|e| format!("No pude abrir {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    parse_mtl(BufReader::new(file), &path.display().to_string(), dir)
+}
+
+/// `newmtl`, `Kd`, `Ks`, `Ns` y `map_Kd` (relativo a `dir`); el resto se ignora
+pub fn parse_mtl<R: BufRead>(reader: R, name: &str, dir: &Path) -> Result<Vec<Material>, String> {
+    let mut materials: Vec<Material> = Vec::new();
+    for (n, line) in reader.lines().enumerate() {
+        let err = |msg: String| format!("{}:{}: {}", name, n + 1, msg);
+        let line = line.map_err(|e| err(format!("error de lectura: {e}")))?;
+        let s = line.trim();
+        if s.is_empty() || s.starts_with('#') { continue; }
+
+        let (key, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let rest = rest.trim();
+        if key == "newmtl" {
+            if rest.is_empty() { return Err(err("newmtl sin nombre".into())); }
+            materials.push(Material { name: rest.to_string(), ..Default::default() });
+            continue;
+        }
+        if !matches!(key, "Kd" | "Ks" | "Ns" | "map_Kd") { continue; }
+        let m = materials.last_mut().ok_or_else(|| err(format!("{key} antes de newmtl")))?;
+        match key {
+            "Kd" => { let [r, g, b] = floats(rest, 3, 3, key).map_err(err)?; m.diffuse = Vec3::new(r, g, b); }
+            "Ks" => { let [r, g, b] = floats(rest, 3, 3, key).map_err(err)?; m.specular = Vec3::new(r, g, b); }
+            "Ns" => m.shininess = floats(rest, 1, 1, key).map_err(err)?[0],
+            _ => {
+                // Las opciones (`-s 1 1 1`, ...) van antes del archivo
+                let file = rest.split_whitespace().last().ok_or_else(|| err("map_Kd sin archivo".into()))?;
+                m.diffuse_map = Some(dir.join(file));
+            }
+        }
+    }
+    Ok(materials)
+}
+
+/// Escribe `mesh` como .obj y, si tiene materiales, su .mtl al lado (mismo nombre)
+pub fn save_obj(mesh: &Mesh, path: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    let mtllib = if mesh.materials.is_empty() {
+        None
+    } else {
+        let mtl = path.with_extension("mtl");
+        write_file(&mtl, |w| write_mtl(mesh, w))?;
+        mtl.file_name().map(|f| f.to_string_lossy().into_owned())
+    };
+    write_file(path, |w| write_obj(mesh, w, mtllib.as_deref()))
+}
+
+/// `v` (con `r g b` si la malla tiene colores), `vt`, `vn` y caras `v/vt/vn`;
+/// cada `SubMesh` es un `g` con su `usemtl`
+pub fn write_obj<W: Write>(mesh: &Mesh, w: &mut W, mtllib: Option<&str>) -> io::Result<()> {
+    writeln!(w, "# {} vértices, {} triángulos", mesh.vertices.len(), mesh.indices.len())?;
+    if let Some(lib) = mtllib { writeln!(w, "mtllib {lib}")?; }
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        let p = v.pos;
+        match mesh.colors.get(i) {
+            Some(c) => writeln!(w, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.x, c.y, c.z)?,
+            None => writeln!(w, "v {} {} {}", p.x, p.y, p.z)?,
+        }
+    }
+    for v in &mesh.vertices { writeln!(w, "vt {} {}", v.uv.x, v.uv.y)?; }
+    for v in &mesh.vertices { writeln!(w, "vn {} {} {}", v.nrm.x, v.nrm.y, v.nrm.z)?; }
+
+    let faces = |w: &mut W, tris: &[Triangle]| -> io::Result<()> {
+        for t in tris {
+            let [a, b, c] = [t.i0 + 1, t.i1 + 1, t.i2 + 1];
+            writeln!(w, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        Ok(())
+    };
+    if mesh.submeshes.is_empty() { return faces(w, &mesh.indices); }
+    for (i, sub) in mesh.submeshes.iter().enumerate() {
+        if sub.name.is_empty() { writeln!(w, "g parte{i}")?; } else { writeln!(w, "g {}", sub.name)?; }
+        if let Some(m) = sub.material { writeln!(w, "usemtl {}", material_name(mesh, m))?; }
+        faces(w, mesh.triangles(sub))?;
+    }
+    Ok(())
+}
+
+/// Los materiales de `mesh` en formato .mtl
+pub fn write_mtl<W: Write>(mesh: &Mesh, w: &mut W) -> io::Result<()> {
+    for (i, m) in mesh.materials.iter().enumerate() {
+        writeln!(w, "newmtl {}", material_name(mesh, i))?;
+        writeln!(w, "Kd {} {} {}", m.diffuse.x, m.diffuse.y, m.diffuse.z)?;
+        writeln!(w, "Ks {} {} {}", m.specular.x, m.specular.y, m.specular.z)?;
+        writeln!(w, "Ns {}", m.shininess)?;
+        if let Some(map) = &m.diffuse_map { writeln!(w, "map_Kd {}", map.display())?; }
+    }
+    Ok(())
+}
+
+/// Los materiales sin nombre (p. ej. de un glTF) necesitan uno para `usemtl`
+fn material_name(mesh: &Mesh, i: usize) -> String {
+    match mesh.materials[i].name.as_str() {
+        "" => format!("material{i}"),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::temp_dir;
+
+    #[test]
+    fn parse_faces_basic() {
+        assert_eq!(parse_face_token("3//7").unwrap(), (3, None, Some(7)));
+        assert_eq!(parse_face_token("12").unwrap(), (12, None, None));
+        assert_eq!(parse_face_token("1/2/3").unwrap(), (1, Some(2), Some(3)));
+        assert_eq!(parse_face_token("4/5").unwrap(), (4, Some(5), None));
+        assert_eq!(parse_face_token("-1/-2/-3").unwrap(), (-1, Some(-2), Some(-3)));
+        assert!(parse_face_token("1/2/3/4").is_err());
+        assert!(parse_face_token("x//2").is_err());
+    }
+
+    #[test]
+    fn groups_materials_uvs_and_relative_indices() {
+        let dir = temp_dir("obj");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("mats.mtl"), "\
+newmtl roca
+Kd 0.5 0.4 0.3
+Ks 0.1 0.1 0.1
+Ns 32
+map_Kd -s 1 1 1 tex/roca.png
+newmtl hielo
+Kd 0.8 0.9 1.0
+").unwrap();
+
+        let obj = "\
+mtllib mats.mtl
+o planeta
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl roca
+f 1/1/1 2/2/1 3/3/1
+g casquete
+usemtl hielo
+f -4/-4 -2/-2 -1/-1
+";
+        let mesh = parse_obj(obj.as_bytes(), "test.obj", &dir).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(mesh.indices.len(), 2);
+        assert_eq!(mesh.materials.len(), 2);
+        let roca = &mesh.materials[0];
+        assert_eq!((roca.diffuse, roca.shininess), (Vec3::new(0.5, 0.4, 0.3), 32.0));
+        assert_eq!(roca.diffuse_map.as_deref(), Some(dir.join("tex/roca.png").as_path()));
+
+        assert_eq!(mesh.submeshes, vec![
+            SubMesh { name: "planeta".into(), triangles: 0..1, material: Some(0) },
+            SubMesh { name: "casquete".into(), triangles: 1..2, material: Some(1) },
+        ]);
+
+        // -4/-4 es el primer vértice; sin vn, la normal se calcula
+        let tri = mesh.triangles(mesh.submesh_by_name("casquete").unwrap())[0];
+        let v = mesh.vertices[tri.i0 as usize];
+        assert_eq!((v.pos, v.uv), (Vec3::new(0.0, 0.0, 0.0), Vec2::new(0.0, 0.0)));
+        assert!((v.nrm - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-5);
+        // +u va hacia +x
+        assert!((v.tangent.xyz() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut mesh = Mesh::icosphere(1.0, 1);
+        let n = mesh.indices.len();
+        mesh.materials = vec![Material { name: "roca".into(), shininess: 8.0, ..Default::default() }, Material::default()];
+        mesh.submeshes = vec![
+            SubMesh { name: "norte".into(), triangles: 0..n / 2, material: Some(0) },
+            SubMesh { name: "sur".into(), triangles: n / 2..n, material: Some(1) },
+        ];
+        mesh.colors = mesh.vertices.iter().map(|v| v.nrm * 0.5 + Vec3::new(0.5, 0.5, 0.5)).collect();
+
+        let dir = temp_dir("obj_out");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ico.obj");
+        save_obj(&mesh, path.to_str().unwrap()).unwrap();
+        let back = load_obj(path.to_str().unwrap());
+        std::fs::remove_dir_all(&dir).ok();
+        let back = back.unwrap();
+
+        assert_eq!(back.indices.len(), n);
+        assert_eq!(back.submeshes, mesh.submeshes);
+        // El material sin nombre recibe uno al exportar
+        assert_eq!((back.materials[0].shininess, back.materials[1].name.as_str()), (8.0, "material1"));
+        // Los vértices que ningún triángulo usa no se exportan: se compara por esquina
+        for (ta, tb) in mesh.indices.iter().zip(&back.indices) {
+            for (ia, ib) in [(ta.i0, tb.i0), (ta.i1, tb.i1), (ta.i2, tb.i2)] {
+                let (a, b) = (mesh.vertices[ia as usize], back.vertices[ib as usize]);
+                assert_eq!((a.pos, a.uv), (b.pos, b.uv));
+                assert!((a.nrm - b.nrm).length() < 1e-6);
+                assert_eq!(mesh.colors[ia as usize], back.colors[ib as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn errors_name_file_and_line() {
+        let dir = Path::new("");
+        let bad = |src: &str| parse_obj(src.as_bytes(), "m.obj", dir).unwrap_err();
+        assert_eq!(bad("v 0 0 0\nv 1 0\n"), "m.obj:2: v incompleto: se esperaban 3 valores");
+        assert_eq!(bad("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 4\n"), "m.obj:4: índice v 4 fuera de rango (hay 3)");
+        assert_eq!(bad("v 0 0 0\n\nf 1 -2 1\n"), "m.obj:3: índice v -2 fuera de rango (hay 1)");
+        assert_eq!(parse_mtl("Kd 1 1 1\n".as_bytes(), "a.mtl", dir).unwrap_err(), "a.mtl:1: Kd antes de newmtl");
+
+        // Materiales que faltan no son error: la parte queda sin material
+        let mesh = parse_obj("mtllib no_existe.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl None\nf 1 2 3\n".as_bytes(), "m.obj", dir).unwrap();
+        assert_eq!(mesh.submeshes[0].material, None);
+    }
+}
@@ -0,0 +1,116 @@
+//! Órbitas keplerianas: posición de un cuerpo alrededor del origen de su padre a partir de
+//! los elementos orbitales.
+
+use std::f32::consts::{PI, TAU};
+
+use crate::math::{Mat4, Vec3, Vec4, rotation_y};
+use crate::math::mat::rotation_x;
+
+/// Elementos de una órbita elíptica con foco en el origen del padre. El plano de referencia
+/// es el xz del padre y el sentido directo va de +x hacia -z (el de `rotation_y`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Orbit {
+    /// Semieje mayor
+    pub semi_major: f32,
+    /// 0 (círculo) ≤ e < 1
+    pub eccentricity: f32,
+    /// Inclinación (rad) del plano de la órbita respecto del xz
+    pub inclination: f32,
+    /// Longitud del nodo ascendente (rad), medida desde +x
+    pub ascending_node: f32,
+    /// Argumento del periapsis (rad), medido desde el nodo ascendente
+    pub periapsis: f32,
+    /// Período (s); negativo recorre la órbita en sentido retrógrado
+    pub period: f32,
+    /// Anomalía media (rad) en t = 0
+    pub mean_anomaly: f32,
+}
+
+impl Orbit {
+    /// Órbita circular en el plano xz que pasa por `pos` en t = 0 (la altura `pos.y` no
+    /// cuenta), a `speed` rad/s
+    pub fn through(pos: Vec3, speed: f32) -> Self {
+        Self {
+            semi_major: pos.xz().length(),
+            eccentricity: 0.0,
+            inclination: 0.0,
+            ascending_node: 0.0,
+            periapsis: 0.0,
+            period: TAU / speed,
+            mean_anomaly: (-pos.z).atan2(pos.x),
+        }
+    }
+
+    /// Distancia máxima al foco
+    pub fn apoapsis(&self) -> f32 {
+        self.semi_major * (1.0 + self.eccentricity)
+    }
+
+    /// Posición en el tiempo `time`, en el espacio del padre
+    pub fn position(&self, time: f32) -> Vec3 {
+        let e = self.eccentricity;
+        let m = (self.mean_anomaly + TAU * (time / self.period)).rem_euclid(TAU);
+        let (sin_e, cos_e) = eccentric_anomaly(m, e).sin_cos();
+        // En el plano de la órbita: periapsis en +x, avance hacia -z
+        let b = self.semi_major * (1.0 - e * e).sqrt();
+        let p = Vec4::new(self.semi_major * (cos_e - e), 0.0, -b * sin_e, 1.0);
+        (self.orientation() * p).xyz()
+    }
+
+    /// Del plano de la órbita al del padre: periapsis, inclinación sobre la línea de
+    /// nodos y giro de esa línea
+    fn orientation(&self) -> Mat4 {
+        rotation_y(self.ascending_node) * rotation_x(self.inclination) * rotation_y(self.periapsis)
+    }
+}
+
+/// Resuelve la ecuación de Kepler `M = E - e sin E` por Newton
+pub fn eccentric_anomaly(mean: f32, e: f32) -> f32 {
+    // Con excentricidades altas Newton converge mejor empezando en π
+    let mut big_e = if e > 0.8 { PI } else { mean };
+    for _ in 0..20 {
+        let step = (big_e - e * big_e.sin() - mean) / (1.0 - e * big_e.cos());
+        big_e -= step;
+        if step.abs() < 1e-6 { break; }
+    }
+    big_e
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    #[test]
+    fn ellipse_follows_keplers_laws() {
+        let orbit = Orbit {
+            semi_major: 2.0, eccentricity: 0.6, inclination: FRAC_PI_2, ascending_node: 0.0,
+            periapsis: 0.0, period: 8.0, mean_anomaly: 0.0,
+        };
+        for m in [0.1, 1.0, 3.0, 6.0] {
+            let big_e = eccentric_anomaly(m, 0.95);
+            assert!((big_e - 0.95 * big_e.sin() - m).abs() < 1e-5, "M = {m}");
+        }
+        // Periapsis en t = 0 y apoapsis a medio período
+        assert!((orbit.position(0.0) - Vec3::new(0.8, 0.0, 0.0)).length() < 1e-5);
+        assert!((orbit.position(4.0) - Vec3::new(-3.2, 0.0, 0.0)).length() < 1e-4);
+        assert!((orbit.position(8.0) - orbit.position(0.0)).length() < 1e-4);
+        // Inclinada 90° sube tras el nodo ascendente
+        assert!(orbit.position(1.0).y > 0.0 && orbit.position(1.0).z.abs() < 1e-5);
+        // Áreas iguales en tiempos iguales: cerca del periapsis recorre más
+        let swept = |t0: f32| orbit.position(t0).cross(orbit.position(t0 + 0.01)).length();
+        assert!((swept(0.0) - swept(3.0)).abs() / swept(0.0) < 0.02);
+        assert!((orbit.position(0.5) - orbit.position(0.0)).length() > (orbit.position(4.5) - orbit.position(4.0)).length());
+    }
+
+    #[test]
+    fn circular_orbit_matches_a_rotation() {
+        let pos = Vec3::new(-1.5, 0.0, 2.0);
+        let orbit = Orbit::through(pos, -0.4);
+        for t in [0.0, 1.0, 7.5] {
+            let expected = (rotation_y(-0.4 * t) * Vec4::from3(pos, 1.0)).xyz();
+            assert!((orbit.position(t) - expected).length() < 1e-4, "t = {t}");
+        }
+        assert_eq!(orbit.apoapsis(), 2.5);
+    }
+}
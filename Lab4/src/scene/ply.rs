@@ -0,0 +1,409 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::math::{Vec2, Vec3};
+use super::model::{Mesh, Triangle, Vertex, write_file};
+
+/// Carga un .ply ASCII o binario (little o big endian).
+/// - `vertex`: `x y z` y, si están, `nx ny nz`, UV (`u v`, `s t` o `texture_u texture_v`)
+///   y color (`red green blue`, enteros en 0..255 o floats en 0..1).
+/// - `face`: lista `vertex_indices` (o `vertex_index`), polígonos triangulados en abanico.
+/// - Otros elementos y propiedades se leen y se descartan.
+/// - Sin normales se calculan; con UV se calculan tangentes.
+///
+/// Los errores indican `archivo:línea` (ASCII) o `archivo: byte N` (binario).
+pub fn load_ply(path: &str) -> Result<Mesh, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("No pude abrir {}: {}", path, e))?;
+    parse_ply(&bytes, path)
+}
+
+/// Codificación del cuerpo de un .ply
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlyFormat { Ascii, BinaryLittleEndian, BinaryBigEndian }
+
+/// Tipos escalares de PLY
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Scalar { I8, U8, I16, U16, I32, U32, F32, F64 }
+
+impl Scalar {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn is_float(self) -> bool { matches!(self, Scalar::F32 | Scalar::F64) }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    ty: Scalar,
+    /// Tipo del contador si es una lista
+    list: Option<Scalar>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    props: Vec<Property>,
+}
+
+/// Igual que `load_ply` con el archivo ya leído; `name` aparece en los errores
+pub fn parse_ply(bytes: &[u8], name: &str) -> Result<Mesh, String> {
+    let (format, elements, header_len, header_lines) = parse_header(bytes).map_err(|e| format!("{name}:{e}"))?;
+    let mut body = Body { data: bytes, pos: header_len, format, line: header_lines + 1, name };
+
+    let mut mesh = Mesh::default();
+    let (mut has_normal, mut has_uv, mut has_vertices) = (false, false, false);
+    for el in &elements {
+        match el.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| el.props.iter().position(|p| p.list.is_none() && names.contains(&p.name.as_str()));
+                let [x, y, z] = [["x"], ["y"], ["z"]].map(|n| find(&n));
+                let (Some(x), Some(y), Some(z)) = (x, y, z) else {
+                    return Err(format!("{name}: el elemento vertex no tiene x, y, z"));
+                };
+                let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                let uv = [find(&["u", "s", "texture_u"]), find(&["v", "t", "texture_v"])];
+                let color = [find(&["red"]), find(&["green"]), find(&["blue"])];
+                has_normal = normal.iter().all(Option::is_some);
+                has_uv = uv.iter().all(Option::is_some);
+                // Enteros: 0..255
+                let color_scale = |p: usize| if el.props[p].ty.is_float() { 1.0 } else { 1.0 / 255.0 };
+                has_vertices = true;
+
+                let mut values = vec![0.0; el.props.len()];
+                for _ in 0..el.count {
+                    body.record(el, |p, v| values[p] = v, |_, _| {})?;
+                    let mut vtx = Vertex { pos: Vec3::new(values[x] as f32, values[y] as f32, values[z] as f32), ..Default::default() };
+                    if let [Some(nx), Some(ny), Some(nz)] = normal {
+                        vtx.nrm = Vec3::new(values[nx] as f32, values[ny] as f32, values[nz] as f32).normalize();
+                    }
+                    if let [Some(u), Some(v)] = uv {
+                        vtx.uv = Vec2::new(values[u] as f32, values[v] as f32);
+                    }
+                    if let [Some(r), Some(g), Some(b)] = color {
+                        let [r, g, b] = [r, g, b].map(|p| (values[p] * color_scale(p)) as f32);
+                        mesh.colors.push(Vec3::new(r, g, b));
+                    }
+                    mesh.vertices.push(vtx);
+                }
+            }
+            "face" => {
+                let list = el.props.iter().position(|p| p.list.is_some() && matches!(p.name.as_str(), "vertex_indices" | "vertex_index"))
+                    .ok_or_else(|| format!("{name}: el elemento face no tiene vertex_indices"))?;
+                let mut face: Vec<f64> = Vec::new();
+                for _ in 0..el.count {
+                    face.clear();
+                    body.skip_ws();
+                    let at = body.location();
+                    body.record(el, |_, _| {}, |p, v| if p == list { face.push(v) })?;
+                    if face.len() < 3 { return Err(format!("{at}: cara con {} vértices (mínimo 3)", face.len())); }
+                    let count = mesh.vertices.len();
+                    if let Some(bad) = face.iter().find(|&&i| i < 0.0 || i as usize >= count) {
+                        return Err(format!("{at}: índice de vértice {bad} fuera de rango (hay {count})"));
+                    }
+                    for i in 2..face.len() {
+                        mesh.indices.push(Triangle { i0: face[0] as u32, i1: face[i - 1] as u32, i2: face[i] as u32 });
+                    }
+                }
+            }
+            _ => for _ in 0..el.count { body.record(el, |_, _| {}, |_, _| {})?; },
+        }
+    }
+    if !has_vertices { return Err(format!("{name}: no hay elemento vertex")); }
+
+    if !has_normal { mesh.recompute_normals(); }
+    if has_uv { mesh.recompute_tangents(); }
+    Ok(mesh)
+}
+
+/// Lee la cabecera hasta `end_header`; devuelve el formato, los elementos,
+/// dónde empieza el cuerpo y cuántas líneas ocupó la cabecera.
+/// Los errores empiezan con `línea: `.
+fn parse_header(bytes: &[u8]) -> Result<(PlyFormat, Vec<Element>, usize, usize), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos = 0;
+    let mut n = 0;
+    loop {
+        n += 1;
+        let end = bytes[pos..].iter().position(|&b| b == b'\n').map(|i| pos + i)
+            .ok_or_else(|| format!("{n}: la cabecera no termina en end_header"))?;
+        let line = std::str::from_utf8(&bytes[pos..end]).map_err(|_| format!("{n}: cabecera con UTF-8 inválido"))?.trim();
+        pos = end + 1;
+        let err = |msg: String| format!("{n}: {msg}");
+
+        if n == 1 {
+            if line != "ply" { return Err(err("no es un archivo PLY (falta 'ply')".into())); }
+            continue;
+        }
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["end_header"] => break,
+            [] | ["comment" | "obj_info", ..] => {}
+            ["format", f, _version] => format = Some(match *f {
+                "ascii" => PlyFormat::Ascii,
+                "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                _ => return Err(err(format!("formato '{f}' desconocido"))),
+            }),
+            ["element", el, count] => {
+                let count = count.parse().map_err(|_| err(format!("cantidad de {el} inválida: '{count}'")))?;
+                elements.push(Element { name: el.to_string(), count, props: Vec::new() });
+            }
+            ["property", rest @ ..] => {
+                let el = elements.last_mut().ok_or_else(|| err("property antes de element".into()))?;
+                let ty = |s: &str| Scalar::parse(s).ok_or_else(|| err(format!("tipo '{s}' desconocido")));
+                let prop = match rest {
+                    ["list", count, item, name] => Property { name: name.to_string(), ty: ty(item)?, list: Some(ty(count)?) },
+                    [t, name] => Property { name: name.to_string(), ty: ty(t)?, list: None },
+                    _ => return Err(err(format!("property inválida: '{line}'"))),
+                };
+                if prop.list.is_some_and(Scalar::is_float) {
+                    return Err(err("el contador de una lista debe ser entero".into()));
+                }
+                el.props.push(prop);
+            }
+            _ => return Err(err(format!("línea de cabecera desconocida: '{line}'"))),
+        }
+    }
+    let format = format.ok_or_else(|| format!("{n}: falta la línea format"))?;
+    Ok((format, elements, pos, n))
+}
+
+/// Cursor sobre el cuerpo, en ASCII o binario
+struct Body<'a> {
+    /// Todo el archivo; `pos` arranca después de la cabecera
+    data: &'a [u8],
+    pos: usize,
+    format: PlyFormat,
+    /// Línea actual (solo ASCII)
+    line: usize,
+    name: &'a str,
+}
+
+impl Body<'_> {
+    fn location(&self) -> String {
+        match self.format {
+            PlyFormat::Ascii => format!("{}:{}", self.name, self.line),
+            _ => format!("{}: byte {}", self.name, self.pos),
+        }
+    }
+
+    /// Lee una instancia de `el`: `scalar(i, v)` por cada propiedad simple y
+    /// `item(i, v)` por cada elemento de las listas
+    fn record(&mut self, el: &Element, mut scalar: impl FnMut(usize, f64), mut item: impl FnMut(usize, f64)) -> Result<(), String> {
+        for (i, p) in el.props.iter().enumerate() {
+            match p.list {
+                None => scalar(i, self.read(p.ty)?),
+                Some(count_ty) => {
+                    let count = self.read(count_ty)?;
+                    if count < 0.0 { return Err(format!("{}: lista con {count} elementos", self.location())); }
+                    for _ in 0..count as usize { item(i, self.read(p.ty)?); }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, ty: Scalar) -> Result<f64, String> {
+        if self.format == PlyFormat::Ascii { return self.read_ascii(ty); }
+
+        let size = ty.size();
+        let Some(raw) = self.data.get(self.pos..self.pos + size) else {
+            return Err(format!("{}: archivo truncado", self.location()));
+        };
+        let mut b = [0u8; 8];
+        b[..size].copy_from_slice(raw);
+        if self.format == PlyFormat::BinaryBigEndian { b[..size].reverse(); }
+        self.pos += size;
+        Ok(match ty {
+            Scalar::I8 => b[0] as i8 as f64,
+            Scalar::U8 => b[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(b),
+        })
+    }
+
+    /// En ASCII avanza hasta el próximo valor, contando líneas
+    fn skip_ws(&mut self) {
+        if self.format != PlyFormat::Ascii { return; }
+        while let Some(&c) = self.data.get(self.pos) {
+            if !c.is_ascii_whitespace() { break; }
+            if c == b'\n' { self.line += 1; }
+            self.pos += 1;
+        }
+    }
+
+    fn read_ascii(&mut self, ty: Scalar) -> Result<f64, String> {
+        self.skip_ws();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|c| !c.is_ascii_whitespace()) { self.pos += 1; }
+        if start == self.pos { return Err(format!("{}: faltan valores", self.location())); }
+
+        let tok = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or("?");
+        let value = match ty {
+            Scalar::F32 | Scalar::F64 => tok.parse::<f64>().ok(),
+            _ => tok.parse::<i64>().ok().map(|i| i as f64),
+        };
+        value.ok_or_else(|| format!("{}: '{tok}' no es un {ty:?}", self.location()))
+    }
+}
+
+/// Escribe `mesh` como .ply
+pub fn save_ply(mesh: &Mesh, path: &str, format: PlyFormat) -> Result<(), String> {
+    write_file(Path::new(path), |w| write_ply(mesh, w, format))
+}
+
+/// Posición, normal, UV (`s t`), color (`red green blue` en 0..255) si la malla tiene,
+/// y caras como `vertex_indices`
+pub fn write_ply<W: Write>(mesh: &Mesh, w: &mut W, format: PlyFormat) -> io::Result<()> {
+    let has_color = !mesh.colors.is_empty();
+    let name = match format {
+        PlyFormat::Ascii => "ascii",
+        PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        PlyFormat::BinaryBigEndian => "binary_big_endian",
+    };
+    writeln!(w, "ply\nformat {name} 1.0\nelement vertex {}", mesh.vertices.len())?;
+    for p in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] { writeln!(w, "property float {p}")?; }
+    if has_color { writeln!(w, "property uchar red\nproperty uchar green\nproperty uchar blue")?; }
+    writeln!(w, "element face {}\nproperty list uchar uint vertex_indices\nend_header", mesh.indices.len())?;
+
+    let big = format == PlyFormat::BinaryBigEndian;
+    let f32s = |w: &mut W, vals: &[f32]| -> io::Result<()> {
+        for v in vals { w.write_all(&if big { v.to_be_bytes() } else { v.to_le_bytes() })?; }
+        Ok(())
+    };
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        let vals = [v.pos.x, v.pos.y, v.pos.z, v.nrm.x, v.nrm.y, v.nrm.z, v.uv.x, v.uv.y];
+        let rgb = mesh.colors.get(i).map(|c| [c.x, c.y, c.z].map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8));
+        if format == PlyFormat::Ascii {
+            let line: Vec<String> = vals.iter().map(f32::to_string)
+                .chain(rgb.iter().flatten().map(u8::to_string)).collect();
+            writeln!(w, "{}", line.join(" "))?;
+        } else {
+            f32s(w, &vals)?;
+            if let Some(rgb) = rgb { w.write_all(&rgb)?; }
+        }
+    }
+    for t in &mesh.indices {
+        let idx = [t.i0, t.i1, t.i2];
+        if format == PlyFormat::Ascii {
+            writeln!(w, "3 {} {} {}", idx[0], idx[1], idx[2])?;
+        } else {
+            w.write_all(&[3])?;
+            for i in idx { w.write_all(&if big { i.to_be_bytes() } else { i.to_le_bytes() })?; }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+ply
+format {FORMAT} 1.0
+comment un cuadrado con UV y un elemento extra
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property float s
+property float t
+element face 1
+property uchar flags
+property list uchar int vertex_indices
+element edge 1
+property list uchar int vertex_index
+end_header
+";
+
+    #[test]
+    fn ascii_and_binary_give_the_same_mesh() {
+        let ascii = HEADER.replace("{FORMAT}", "ascii") + "\
+0 0 0 255 0 0
+1 0 0 255 1 0
+1 1 0 255 1 1
+0 1 0 255 0 1
+7 4 0 1 2 3
+2 0 2
+";
+        let mut binary = HEADER.replace("{FORMAT}", "binary_big_endian").into_bytes();
+        for [x, y, s, t] in [[0.0f32, 0.0, 0.0, 0.0], [1.0, 0.0, 1.0, 0.0], [1.0, 1.0, 1.0, 1.0], [0.0, 1.0, 0.0, 1.0]] {
+            for f in [x, y, 0.0] { binary.extend(f.to_be_bytes()); }
+            binary.push(255);
+            for f in [s, t] { binary.extend(f.to_be_bytes()); }
+        }
+        binary.extend([7, 4]);
+        for i in [0i32, 1, 2, 3] { binary.extend(i.to_be_bytes()); }
+        binary.push(2);
+        for i in [0i32, 2] { binary.extend(i.to_be_bytes()); }
+
+        for bytes in [ascii.as_bytes(), &binary] {
+            let mesh = parse_ply(bytes, "q.ply").unwrap();
+            assert_eq!((mesh.vertices.len(), mesh.indices.len()), (4, 2));
+            let v = mesh.vertices[2];
+            assert_eq!((v.pos, v.uv), (Vec3::new(1.0, 1.0, 0.0), Vec2::new(1.0, 1.0)));
+            assert!((v.nrm - Vec3::new(0.0, 0.0, 1.0)).length() < 1e-5);
+            assert!((v.tangent.xyz() - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn writes_every_format_and_reads_it_back() {
+        let mut mesh = Mesh::uv_sphere(1.0, 8, 4);
+        mesh.colors = mesh.vertices.iter().map(|v| v.nrm * 0.5 + Vec3::new(0.5, 0.5, 0.5)).collect();
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian, PlyFormat::BinaryBigEndian] {
+            let mut bytes = Vec::new();
+            write_ply(&mesh, &mut bytes, format).unwrap();
+            let back = parse_ply(&bytes, "out.ply").unwrap();
+            assert_eq!(back.indices.len(), mesh.indices.len());
+            for (i, (a, b)) in mesh.vertices.iter().zip(&back.vertices).enumerate() {
+                assert_eq!((a.pos, a.uv), (b.pos, b.uv), "{format:?}");
+                assert!((a.nrm - b.nrm).length() < 1e-6);
+                // El color pasa por 8 bits
+                assert!((mesh.colors[i] - back.colors[i]).length() < 0.5 / 255.0 * 3f32.sqrt());
+            }
+        }
+    }
+
+    #[test]
+    fn errors_name_file_and_position() {
+        let bad = |src: &[u8]| parse_ply(src, "m.ply").unwrap_err();
+        let header = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\n\
+element face 1\nproperty list uchar uint vertex_indices\nend_header\n";
+        assert_eq!(bad(format!("{header}0 0 0\n1 0 x\n").as_bytes()), "m.ply:11: 'x' no es un F32");
+        assert_eq!(bad(format!("{header}0 0 0\n1 0 0\n0 1 0\n3 0 1 5\n").as_bytes()), "m.ply:13: índice de vértice 5 fuera de rango (hay 3)");
+        assert_eq!(bad(b"ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n"), "m.ply:4: tipo 'quad' desconocido");
+        assert_eq!(bad(b"obj\n"), "m.ply:1: no es un archivo PLY (falta 'ply')");
+
+        let binary = header.replace("ascii", "binary_little_endian");
+        let at = binary.len() + 4;
+        assert_eq!(bad(format!("{binary}\0\0\0\0").as_bytes()), format!("m.ply: byte {at}: archivo truncado"));
+    }
+}
@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use crate::math::{Vec3, Mat4};
+use crate::math::mat::{translate, scale};
+use crate::renderer::{Framebuffer, Uniforms, PlanetParams, ShadowMap};
+use crate::renderer::buffers::Color;
+use crate::renderer::pipeline::{PipelineState, CullMode};
+use crate::renderer::raster::DrawStats;
+use crate::shaders::{ShaderKind, ShaderParams};
+use crate::shaders::rocky_planet::Rocky;
+use super::{Camera, Mesh, Orbit};
+use super::graph::{Node, Scene};
+use super::model::MeshSource;
+
+/// Color de fondo del espacio
+pub const BACKGROUND: Color = Color::rgb(5, 8, 12);
+
+/// Qué muestra la escena de planetas. Lo comparten la ventana y el modo headless.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Showcase {
+    /// Planeta mostrado cuando `show_all` es falso
+    pub shader: ShaderKind,
+    /// Fila con todos los planetas
+    pub show_all: bool,
+    pub show_rings: bool,
+    pub show_moon: bool,
+    pub shadows: bool,
+    /// Altura del relieve geométrico del planeta rocoso (fracción del radio; 0 = esfera lisa)
+    pub terrain: f32,
+}
+
+impl Default for Showcase {
+    fn default() -> Self {
+        Self { shader: ShaderKind::Flat, show_all: true, show_rings: true, show_moon: true, shadows: true, terrain: Rocky::default().displacement }
+    }
+}
+
+/// Una malla (o de dónde sacarla) por planeta: `default`, salvo override para ese shader
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlanetMeshes<T> {
+    pub default: T,
+    pub overrides: Vec<(ShaderKind, T)>,
+}
+
+impl<T> PlanetMeshes<T> {
+    pub fn new(default: T) -> Self { Self { default, overrides: Vec::new() } }
+
+    pub fn get(&self, kind: ShaderKind) -> &T {
+        self.overrides.iter().find(|(k, _)| *k == kind).map_or(&self.default, |(_, m)| m)
+    }
+
+    /// Reemplaza el override de `kind`, si ya había uno
+    pub fn set(&mut self, kind: ShaderKind, value: T) {
+        self.overrides.retain(|(k, _)| *k != kind);
+        self.overrides.push((kind, value));
+    }
+
+    pub fn try_map<U, E>(&self, mut f: impl FnMut(&T) -> Result<U, E>) -> Result<PlanetMeshes<U>, E> {
+        Ok(PlanetMeshes {
+            default: f(&self.default)?,
+            overrides: self.overrides.iter().map(|(k, m)| Ok((*k, f(m)?))).collect::<Result<_, E>>()?,
+        })
+    }
+}
+
+impl PlanetMeshes<MeshSource> {
+    pub fn load(&self) -> Result<PlanetMeshes<Mesh>, String> { self.try_map(MeshSource::load) }
+}
+
+/// Cámara inicial de la escena
+pub fn default_camera(aspect: f32) -> Camera {
+    Camera { eye: Vec3::new(0.0, 0.0, 4.0), center: Vec3::new(0.0, 0.0, 0.0), aspect, ..Default::default() }
+}
+
+/// Dirección hacia la luz por defecto (sin normalizar)
+pub const LIGHT_DIR: Vec3 = Vec3 { x: 0.5, y: 0.7, z: 0.2 };
+
+/// Uniforms del frame para la cámara y el tiempo de simulación dados
+pub fn frame_uniforms(cam: &Camera, time: f32) -> Uniforms {
+    Uniforms {
+        time,
+        light_dir: LIGHT_DIR.normalize(),
+        view: cam.view(),
+        proj: cam.proj(),
+        model: Mat4::identity(),
+        camera_pos: cam.eye,
+        planet: PlanetParams::default(),
+        shadow: None,
+    }
+}
+
+/// Pasadas de subdivisión adaptativa de la malla del planeta rocoso
+const TERRAIN_PASSES: u32 = 3;
+
+/// Estado del pipeline con que se dibuja cada planeta.
+/// Planetas cerrados descartan su cara trasera. Los anillos son una esfera aplanada: ya
+/// tiene una cara hacia cada lado, así que con back-face culling se ven desde arriba y
+/// abajo sin mezclar las dos capas.
+pub fn pipeline_state(kind: ShaderKind) -> PipelineState {
+    match kind {
+        ShaderKind::Rings => PipelineState { cull: CullMode::Back, ..PipelineState::transparent() },
+        _ => PipelineState { cull: CullMode::Back, ..Default::default() },
+    }
+}
+
+/// Carga las mallas de `sources`. Si `root` tiene un planeta rocoso con relieve, su malla
+/// se subdivide donde el desplazamiento lo pide (el relieve no cambia con el tiempo).
+pub fn load_meshes(sources: &PlanetMeshes<MeshSource>, root: &Node) -> Result<PlanetMeshes<Mesh>, String> {
+    let mut meshes = sources.load()?;
+    if let Some((ShaderParams::Rocky(rocky), _)) = root.find_shader(ShaderKind::Rocky, 0.0)
+        && rocky.displacement > 0.0
+    {
+        let refined = meshes.get(ShaderKind::Rocky).refine_sphere(TERRAIN_PASSES, |a, b| rocky.needs_split(a, b));
+        meshes.set(ShaderKind::Rocky, refined);
+    }
+    Ok(meshes)
+}
+
+/// Fila de "mostrar todos": planeta, posición en x y escala
+const ROW: [(ShaderKind, f32, f32); 5] = [
+    (ShaderKind::Rocky, -3.0, 0.85),
+    (ShaderKind::Gas, -1.5, 0.95),
+    (ShaderKind::SciFi, 0.3, 0.95),
+    (ShaderKind::Lava, 2.0, 0.9),
+    (ShaderKind::Ice, 3.8, 0.85),
+];
+
+/// Órbita alrededor del origen y giro propio de cada planeta, en rad/s
+fn motion(kind: ShaderKind) -> (f32, f32) {
+    match kind {
+        ShaderKind::Rocky => (0.35, 0.95),
+        ShaderKind::Gas | ShaderKind::Rings => (0.35, 0.35),
+        ShaderKind::SciFi => (0.6, 0.6),
+        ShaderKind::Lava => (0.45, 0.95),
+        ShaderKind::Ice => (0.3, 0.65),
+        ShaderKind::Moon | ShaderKind::Flat => (0.0, 0.0),
+    }
+}
+
+impl Showcase {
+    /// Parámetros del shader de `kind` en esta escena
+    pub fn shader_params(&self, kind: ShaderKind) -> ShaderParams {
+        match kind {
+            ShaderKind::Rocky => ShaderParams::Rocky(Rocky { displacement: self.terrain, ..Default::default() }),
+            other => ShaderParams::new(other),
+        }
+    }
+
+    /// Carga las mallas con `load_meshes`. Usa la fila completa, así el relieve del
+    /// planeta rocoso está listo aunque se elija después (en la ventana).
+    pub fn load_meshes(&self, sources: &PlanetMeshes<MeshSource>) -> Result<PlanetMeshes<Mesh>, String> {
+        load_meshes(sources, &Showcase { show_all: true, ..*self }.graph())
+    }
+
+    /// Radio de la esfera (centrada en el origen) que contiene la escena:
+    /// fila de planetas con órbitas, o un planeta con luna y anillos
+    pub fn radius(&self) -> f32 {
+        if self.show_all { 6.5 } else { 3.0 }
+    }
+
+    /// Grafo de la escena: un nodo por planeta, que orbita el origen, con el cuerpo
+    /// (escala y giro propio), la luna o los anillos como hijos
+    pub fn graph(&self) -> Node {
+        let systems = if self.show_all {
+            ROW.iter().map(|&(kind, x, s)| self.system(kind, Vec3::new(x, 0.0, 0.0), s)).collect()
+        } else {
+            vec![self.system(self.shader, Vec3::ZERO, 1.0)]
+        };
+        Node::group("showcase", Mat4::identity(), systems)
+    }
+
+    fn system(&self, kind: ShaderKind, pos: Vec3, s: f32) -> Node {
+        let mut children = vec![self.body(kind, s)];
+        if kind == ShaderKind::Gas && self.show_rings { children.push(self.body(ShaderKind::Rings, s)); }
+        if kind == ShaderKind::Rocky && self.show_moon { children.push(self.body(ShaderKind::Moon, s)); }
+        Node { orbit: Some(Orbit::through(pos, motion(kind).0)), ..Node::group(kind.name(), Mat4::identity(), children) }
+    }
+
+    /// Nodo que dibuja `kind` dentro de su sistema de escala `s`
+    fn body(&self, kind: ShaderKind, s: f32) -> Node {
+        let node = Node {
+            name: kind.name().into(),
+            shader: Some(self.shader_params(kind)),
+            state: pipeline_state(kind),
+            ..Default::default()
+        };
+        match kind {
+            // La luna gira alrededor del planeta a su propia escala, sin heredar la de él
+            ShaderKind::Moon => Node {
+                orbit: Some(Orbit::through(Vec3::new(2.4, 0.0, 0.0), -0.4)),
+                transform: translate(Vec3::new(0.0, 0.45, 0.0)) * scale(Vec3::new(0.35, 0.35, 0.35)),
+                ..node
+            },
+            _ => Node { spin: motion(kind).1, transform: scale(Vec3::new(s, s, s)), ..node },
+        }
+    }
+
+    /// Escena con la cámara por defecto, la luz de `frame_uniforms` y `graph`
+    pub fn scene(&self) -> Scene {
+        Scene {
+            camera: default_camera(16.0 / 9.0),
+            light_dir: LIGHT_DIR.normalize(),
+            radius: self.radius(),
+            shadows: self.shadows,
+            root: self.graph(),
+            gravity: None,
+        }
+    }
+
+    /// Limpia el framebuffer y dibuja el frame completo (ver `Scene::render`)
+    pub fn render(&self, fb: &mut Framebuffer, meshes: &PlanetMeshes<Mesh>, uniforms: &Uniforms,
+                  shadow_map: &mut Option<Arc<ShadowMap>>) -> DrawStats {
+        self.scene().render(fb, meshes, uniforms, shadow_map)
+    }
+}
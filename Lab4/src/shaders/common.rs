@@ -1,5 +1,5 @@
 use crate::math::{Vec3, Vec4};
-use crate::renderer::{buffers::Color};
+use crate::renderer::buffers::ColorF;
 use crate::renderer::uniforms::Uniforms;
 use crate::math::fbm;
 
@@ -9,9 +9,45 @@ use crate::math::fbm;
     Vec3::new(lerp(a.x,b.x,t), lerp(a.y,b.y,t), lerp(a.z,b.z,t))
 }
 
+/// Radiancia lineal: solo se descartan negativos, el destino decide si recorta
 #[inline]
-pub fn to_color(v: Vec3) -> Color {
-    Color::from_f32_rgb(v.x, v.y, v.z)
+pub fn to_color(v: Vec3) -> ColorF {
+    ColorF::rgb(v.x.max(0.0), v.y.max(0.0), v.z.max(0.0))
+}
+
+/// Color con alpha real (no premultiplicado) para pases con mezcla
+#[inline]
+pub fn to_color_a(v: Vec3, a: f32) -> ColorF {
+    ColorF::rgba(v.x.max(0.0), v.y.max(0.0), v.z.max(0.0), a.clamp(0.0, 1.0))
+}
+
+/// Visibilidad de la luz direccional (1 = iluminado, 0 = en sombra) con PCF
+/// bilineal sobre `(2*radius+1)²` texels del shadow map. Sin shadow map devuelve 1.
+pub fn shadow_pcf(u: &Uniforms, pos_ws: Vec3, nrm_ws: Vec3, radius: i32) -> f32 {
+    let Some(map) = u.shadow.as_deref() else { return 1.0 };
+    let (x, y, z) = map.project(pos_ws, nrm_ws);
+    let (fx, fy) = (x - 0.5, y - 0.5);
+    let (x0, y0) = (fx.floor() as i32, fy.floor() as i32);
+    let (tx, ty) = (fx - fx.floor(), fy - fy.floor());
+    let lit = |x: i32, y: i32| if z <= map.depth_at(x, y) { 1.0 } else { 0.0 };
+
+    let mut sum = 0.0;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (x, y) = (x0 + dx, y0 + dy);
+            let top = lerp(lit(x, y), lit(x + 1, y), tx);
+            let bot = lerp(lit(x, y + 1), lit(x + 1, y + 1), tx);
+            sum += lerp(top, bot, ty);
+        }
+    }
+    let k = (2*radius + 1) as f32;
+    sum / (k*k)
+}
+
+/// `shadow_pcf` con el kernel 3x3 que usan los shaders
+#[inline]
+pub fn shadow(u: &Uniforms, pos_ws: Vec3, nrm_ws: Vec3) -> f32 {
+    shadow_pcf(u, pos_ws, nrm_ws, 1)
 }
 
 #[inline]
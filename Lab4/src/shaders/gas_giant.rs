@@ -1,5 +1,5 @@
-use crate::math::{Vec3, Vec4, rotation_y};
-use crate::renderer::{buffers::Color, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
+use crate::math::{Vec3, Vec4};
+use crate::renderer::{buffers::ColorF, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
 use super::common::*;
 
 #[derive(Copy, Clone, Debug)]
@@ -19,16 +19,18 @@ impl Default for Gas {
 }
 
 impl Gas {
-    fn lat_from_normal(n_ws: Vec3) -> f32 {
-        latitude(n_ws) // [0,1]
+    fn lat_from_normal(n: Vec3) -> f32 {
+        latitude(n) // [0,1]
     }
 
-    fn color_layers(&self, p_ws: Vec3, n_ws: Vec3, view_dir: Vec3, u: &Uniforms) -> Vec3 {
+    /// `p_os` en espacio del objeto: bandas y mancha giran con el planeta
+    fn color_layers(&self, p_os: Vec3, n_ws: Vec3, view_dir: Vec3, vis: f32, u: &Uniforms) -> Vec3 {
+        let n_os = p_os.normalize();
         // Bandas por latitud + turbulencia
-        let lat = Self::lat_from_normal(n_ws); // 0 en sur, 1 en norte
+        let lat = Self::lat_from_normal(n_os); // 0 en sur, 1 en norte
         let phi = lat*std::f32::consts::TAU*self.band_freq;
 
-        let turb = fbm_3d(p_ws + Vec3::new(3.2,7.7,1.5), 4, 2.0, 0.5, u.planet.noise_scale*1.4);
+        let turb = fbm_3d(p_os + Vec3::new(3.2,7.7,1.5), 4, 2.0, 0.5, u.planet.noise_scale*1.4);
         let s = (phi + turb*3.5).sin()*0.5 + 0.5; // 0..1 ondulado
 
         let bands = lerp3(self.main_a, self.main_b, s);
@@ -36,14 +38,15 @@ impl Gas {
         // Mancha  que rota con el planeta
         let _spot_phase = (u.time*0.4).sin()*0.5 + 0.5;
         let spot_dir = Vec3::new(1.0, 0.0, 0.0);
-        let dot_spot = saturate(n_ws.dot(spot_dir));
+        let dot_spot = saturate(n_os.dot(spot_dir));
         let spot = (dot_spot.powf(50.0)) * 0.6; 
         let bands_spot = bands * (1.0 - spot) + Vec3::new(0.7,0.35,0.2)*spot;
 
         // Suave rim
         let rim_k = rim(n_ws, view_dir, 2.2) * 0.35;
 
-        (bands_spot + Vec3::new(0.3,0.35,0.4)*rim_k).clamp01()
+        // Sombra de anillos/lunas: las bandas no tienen lambert, se oscurecen directo
+        bands_spot*(0.3 + 0.7*vis) + Vec3::new(0.3,0.35,0.4)*rim_k
     }
 }
 
@@ -51,19 +54,17 @@ impl Shader for Gas {
     fn name(&self) -> &'static str { "GasGiant" }
 
     fn vertex(&mut self, vin: VertexIn, u: &Uniforms) -> VertexOut {
-        let rot = rotation_y(u.time * u.planet.rotation_speed * 0.7);
-        let model = rot * u.model;
+        let clip = u.proj * u.view * u.model * Vec4::from3(vin.pos, 1.0);
+        let pos_ws = (u.model * Vec4::from3(vin.pos, 1.0)).xyz();
+        let nrm_ws = (u.model * Vec4::from3(vin.nrm, 0.0)).xyz().normalize();
 
-        let clip = u.proj * u.view * model * Vec4::from3(vin.pos, 1.0);
-        let pos_ws = (model * Vec4::from3(vin.pos, 1.0)).xyz();
-        let nrm_ws = (model * Vec4::from3(vin.nrm, 0.0)).xyz().normalize();
-
-        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv }
+        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv, pos_os: vin.pos }
     }
 
-    fn fragment(&mut self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> Color {
+    fn fragment(&self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> ColorF {
         let view_dir = (u.camera_pos - vary.pos_ws).normalize();
-        let c = self.color_layers(vary.pos_ws, vary.nrm_ws, view_dir, u);
+        let vis = shadow(u, vary.pos_ws, vary.nrm_ws);
+        let c = self.color_layers(vary.pos_os, vary.nrm_ws, view_dir, vis, u);
         to_color(c)
     }
 }
@@ -22,15 +22,121 @@ pub enum ShaderKind {
     Ice,
 }
 
-pub fn make_shader(kind: ShaderKind) -> Box<dyn Shader> {
-    match kind {
-        ShaderKind::Rocky => Box::new(rocky_planet::Rocky::default()),
-        ShaderKind::Gas   => Box::new(gas_giant::Gas::default()),
-        ShaderKind::SciFi => Box::new(scifi_planet::SciFi::default()),
-        ShaderKind::Rings => Box::new(rings_vs::Rings::default()),
-        ShaderKind::Moon  => Box::new(moon_vs::Moon::default()),
-        ShaderKind::Flat  => Box::new(flat::Flat::default()), 
-        ShaderKind::Lava  => Box::new(lava::Lava::default()),
-        ShaderKind::Ice   => Box::new(ice::Ice::default()),
+impl ShaderKind {
+    /// Nombre en línea de comandos: `rocky`, `gas`, `scifi`, `rings`, `moon`, `flat`, `lava`, `ice`
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "rocky" => Some(ShaderKind::Rocky),
+            "gas"   => Some(ShaderKind::Gas),
+            "scifi" => Some(ShaderKind::SciFi),
+            "rings" => Some(ShaderKind::Rings),
+            "moon"  => Some(ShaderKind::Moon),
+            "flat"  => Some(ShaderKind::Flat),
+            "lava"  => Some(ShaderKind::Lava),
+            "ice"   => Some(ShaderKind::Ice),
+            _ => None,
+        }
+    }
+
+    /// Inverso de `parse`
+    pub fn name(self) -> &'static str {
+        match self {
+            ShaderKind::Rocky => "rocky",
+            ShaderKind::Gas   => "gas",
+            ShaderKind::SciFi => "scifi",
+            ShaderKind::Rings => "rings",
+            ShaderKind::Moon  => "moon",
+            ShaderKind::Flat  => "flat",
+            ShaderKind::Lava  => "lava",
+            ShaderKind::Ice   => "ice",
+        }
+    }
+}
+
+/// Un shader con sus parámetros. A diferencia de `Box<dyn Shader>` es `Copy`: un nodo de la
+/// escena lo guarda y arma el shader de cada frame con `build`.
+#[derive(Copy, Clone, Debug)]
+pub enum ShaderParams {
+    Rocky(rocky_planet::Rocky),
+    Gas(gas_giant::Gas),
+    SciFi(scifi_planet::SciFi),
+    Rings(rings_vs::Rings),
+    Moon(moon_vs::Moon),
+    Flat,
+    Lava(lava::Lava),
+    Ice(ice::Ice),
+}
+
+impl ShaderParams {
+    /// Parámetros por defecto de `kind`
+    pub fn new(kind: ShaderKind) -> Self {
+        match kind {
+            ShaderKind::Rocky => Self::Rocky(Default::default()),
+            ShaderKind::Gas   => Self::Gas(Default::default()),
+            ShaderKind::SciFi => Self::SciFi(Default::default()),
+            ShaderKind::Rings => Self::Rings(Default::default()),
+            ShaderKind::Moon  => Self::Moon(Default::default()),
+            ShaderKind::Flat  => Self::Flat,
+            ShaderKind::Lava  => Self::Lava(Default::default()),
+            ShaderKind::Ice   => Self::Ice(Default::default()),
+        }
+    }
+
+    pub fn kind(&self) -> ShaderKind {
+        match self {
+            Self::Rocky(_) => ShaderKind::Rocky,
+            Self::Gas(_)   => ShaderKind::Gas,
+            Self::SciFi(_) => ShaderKind::SciFi,
+            Self::Rings(_) => ShaderKind::Rings,
+            Self::Moon(_)  => ShaderKind::Moon,
+            Self::Flat     => ShaderKind::Flat,
+            Self::Lava(_)  => ShaderKind::Lava,
+            Self::Ice(_)   => ShaderKind::Ice,
+        }
     }
+
+    /// Parámetros editables por nombre (los de los archivos de escena), en orden de declaración
+    pub fn fields(&mut self) -> Vec<(&'static str, Param<'_>)> {
+        use Param::{Number as N, Vec3 as V};
+        match self {
+            Self::Rocky(s) => vec![
+                ("sand", V(&mut s.sand)), ("rust", V(&mut s.rust)), ("basalt", V(&mut s.basalt)),
+                ("spec_intensity", N(&mut s.spec_intensity)), ("spec_power", N(&mut s.spec_power)),
+                ("displacement", N(&mut s.displacement)),
+            ],
+            Self::Gas(s) => vec![("main_a", V(&mut s.main_a)), ("main_b", V(&mut s.main_b)), ("band_freq", N(&mut s.band_freq))],
+            Self::SciFi(s) => vec![
+                ("layer0", V(&mut s.layer0)), ("layer1", V(&mut s.layer1)), ("layer2", V(&mut s.layer2)),
+                ("layer3", V(&mut s.layer3)), ("glow_color", V(&mut s.glow_color)), ("noise_scale", N(&mut s.noise_scale)),
+            ],
+            Self::Rings(s) => vec![("inner", N(&mut s.inner)), ("outer", N(&mut s.outer)), ("tilt", N(&mut s.tilt))],
+            Self::Lava(s) => vec![("glow", V(&mut s.glow))],
+            Self::Ice(s) => vec![("frost", V(&mut s.frost))],
+            Self::Moon(_) | Self::Flat => Vec::new(),
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Shader> {
+        match *self {
+            Self::Rocky(s) => Box::new(s),
+            Self::Gas(s)   => Box::new(s),
+            Self::SciFi(s) => Box::new(s),
+            Self::Rings(s) => Box::new(s),
+            Self::Moon(s)  => Box::new(s),
+            Self::Flat     => Box::new(flat::Flat),
+            Self::Lava(s)  => Box::new(s),
+            Self::Ice(s)   => Box::new(s),
+        }
+    }
+}
+
+/// Referencia a un parámetro de `ShaderParams::fields`
+pub enum Param<'a> {
+    Number(&'a mut f32),
+    /// Colores (RGB lineal 0..1)
+    Vec3(&'a mut crate::math::Vec3),
+}
+
+pub fn make_shader(kind: ShaderKind) -> Box<dyn Shader> {
+    ShaderParams::new(kind).build()
 }
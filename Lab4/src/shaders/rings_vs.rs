@@ -1,12 +1,12 @@
-use crate::math::{Vec3, Vec4, rotation_y};
+use crate::math::{Vec3, Vec4};
 use crate::math::mat::rotation_x;
-use crate::renderer::{buffers::Color, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
+use crate::renderer::{buffers::ColorF, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
 use super::common::*;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Rings {
-    pub inner: f32, // radio interno
-    pub outer: f32, // radio externo
+    pub inner: f32, // radio interno, en el espacio del disco (sin la escala del nodo)
+    pub outer: f32, // radio externo, ídem
     pub tilt: f32,  // inclinación en radianes
 }
 
@@ -24,33 +24,28 @@ impl Shader for Rings {
     let mut p = vin.pos;
     p.y *= 0.03;            // aplana
     p.x *= 1.6; p.z *= 1.6; // ensancha
-    let tilt_m = rotation_x(self.tilt);
-    let rot = rotation_y(u.time * u.planet.rotation_speed * 0.7);
-    let model = rot * tilt_m * u.model;
+    // Inclinado respecto del planeta: el nodo de los anillos pone posición y giro
+    let model = u.model * rotation_x(self.tilt);
 
         let clip = u.proj * u.view * model * Vec4::from3(p, 1.0);
         let pos_ws = (model * Vec4::from3(p, 1.0)).xyz();
-        let nrm_ws = Vec3::new(0.0, 1.0, 0.0); 
+        // Normal del plano del disco, con la inclinación propia y la del nodo
+        let nrm_ws = (model * Vec4::new(0.0, 1.0, 0.0, 0.0)).xyz().normalize();
 
-        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv }
+        // `pos_os` en espacio del disco: las bandas giran y se mueven con los anillos
+        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv, pos_os: p }
     }
 
-    fn fragment(&mut self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> Color {
-        let r = (vary.pos_ws.x * vary.pos_ws.x + vary.pos_ws.z * vary.pos_ws.z).sqrt();
-        let bands = (r * 8.0 + fbm_3d(vary.pos_ws * 0.5 + Vec3::new(1.2,0.0,2.3), 3, 2.0, 0.5, 0.8)).sin() * 0.5 + 0.5;
-        let streaks = (r * 120.0 + fbm_3d(vary.pos_ws * 5.0, 2, 2.0, 0.5, 1.4)).sin() * 0.5 + 0.5;
+    fn fragment(&self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> ColorF {
+        let p = vary.pos_os;
+        let r = p.xz().length();
+        let bands = (r * 8.0 + fbm_3d(p * 0.5 + Vec3::new(1.2,0.0,2.3), 3, 2.0, 0.5, 0.8)).sin() * 0.5 + 0.5;
+        let streaks = (r * 120.0 + fbm_3d(p * 5.0, 2, 2.0, 0.5, 1.4)).sin() * 0.5 + 0.5;
         let col_a = Vec3::new(0.92, 0.88, 0.78);
         let col_b = Vec3::new(0.66, 0.60, 0.48);
         let mut base = lerp3(col_a, col_b, bands);
 
-        base = base * (0.9 + 0.45 * streaks);
-
-        let tx = u.model.m[0][3];
-        if tx < -1.5 {
-            base = base + Vec3::new(0.06, 0.08, 0.02);
-        } else if tx > -1.5 && tx < 0.5 {
-            base = base + Vec3::new(0.04, 0.02, 0.0);
-        }
+        base *= 0.9 + 0.45 * streaks;
 
         let inner = if self.inner > 0.0 { self.inner } else { 0.75 };
         let outer = if self.outer > 0.0 { self.outer } else { 1.6 };
@@ -59,8 +54,39 @@ impl Shader for Rings {
         let band_alpha = (edge_in * edge_out).clamp(0.0, 1.0);
         let dust = saturate(1.0 - (r - inner) * 0.8);
         let alpha = band_alpha * (0.25 + 0.75 * dust);
-        let diff = lambert(vary.nrm_ws, u.light_dir) * 0.9 + 0.1;
+        let vis = shadow(u, vary.pos_ws, vary.nrm_ws);
+        let diff = lambert(vary.nrm_ws, u.light_dir) * vis * 0.9 + 0.1;
+
+        to_color_a(base * diff, alpha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+    use crate::math::mat::{scale, translate};
+    use crate::renderer::raster::Varyings;
+
+    #[test]
+    fn bands_follow_the_ring_not_the_world() {
+        // El mismo punto del disco con el sistema en dos lugares de su órbita
+        let shade = |model| {
+            let u = Uniforms { model, ..Default::default() };
+            let mut rings = Rings::default();
+            let vin = VertexIn { pos: Vec3::new(0.6, 0.0, 0.3), nrm: Vec3::new(0.0, 1.0, 0.0), uv: Vec2::ZERO, tangent: Vec4::ZERO };
+            let out = rings.vertex(vin, &u);
+            rings.fragment(&Varyings { pos_ws: out.pos_ws, nrm_ws: out.nrm_ws, uv: out.uv, pos_os: out.pos_os }, &u)
+        };
+        assert_eq!(shade(translate(Vec3::new(-2.0, 0.0, 0.0))), shade(translate(Vec3::new(3.0, 0.0, 1.0))));
+    }
 
-        to_color((base * diff * alpha).clamp01())
+    #[test]
+    fn normal_follows_the_tilt() {
+        let mut rings = Rings { tilt: 0.3, ..Default::default() };
+        let u = Uniforms { model: rotation_x(0.5) * scale(Vec3::new(2.0, 2.0, 2.0)), ..Default::default() };
+        let vin = VertexIn { pos: Vec3::new(0.6, 0.0, 0.3), nrm: Vec3::new(0.0, 1.0, 0.0), uv: Vec2::ZERO, tangent: Vec4::ZERO };
+        let n = rings.vertex(vin, &u).nrm_ws;
+        assert!((n - Vec3::new(0.0, 0.8f32.cos(), 0.8f32.sin())).length() < 1e-5, "{n:?}");
     }
 }
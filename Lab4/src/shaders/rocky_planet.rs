@@ -1,6 +1,6 @@
-use crate::math::{Vec2, Vec3, Vec4, rotation_y};
+use crate::math::{Vec2, Vec3, Vec4};
 use crate::renderer::{
-    buffers::Color,
+    buffers::ColorF,
     uniforms::Uniforms,
     pipeline::{Shader, VertexIn, VertexOut},
 };
@@ -15,20 +15,6 @@ fn smoothstep(e0: f32, e1: f32, x: f32) -> f32 {
 }
 #[inline]
 fn mix3(a: Vec3, b: Vec3, t: f32) -> Vec3 { a * (1.0 - t) + b * t }
-#[inline]
-fn fractf(x: f32) -> f32 { x - x.floor() }
-#[inline]
-fn rot_y(v: Vec3, a: f32) -> Vec3 {
-    let c = a.cos(); let s = a.sin();
-    Vec3::new(c*v.x + s*v.z, v.y, -s*v.x + c*v.z)
-}
-#[inline]
-fn rot_y_uv(n: Vec3, a: f32) -> (f32,f32) {
-    let r = rot_y(n, a);
-    let u = r.z.atan2(r.x) / (2.0*PI) + 0.5;
-    let v = r.y.asin() / PI + 0.5;
-    (u,v)
-}
 
 
 // “fbm” sin texturas: suma de senos
@@ -59,31 +45,31 @@ fn ridge2(x: f32, y: f32, t: f32) -> f32 {
 // Shader: Marte 
 #[derive(Copy, Clone, Debug)]
 pub struct Rocky {
-    pub scale: f32,
-    pub rot_speed: f32,
-    pub orbit_radius: f32,
-    pub orbit_speed: f32,
-    pub orbit_world: bool,
     pub sand:   Vec3, 
     pub rust:   Vec3,  
     pub basalt: Vec3, 
     pub spec_intensity: f32,
     pub spec_power: f32,
+    /// Altura del relieve (cráteres y bordes) como fracción del radio; 0 = esfera lisa
+    pub displacement: f32,
 }
 
+/// Paso angular de las diferencias finitas para la normal del relieve
+const NORMAL_EPS: f32 = 0.004;
+/// Arista más corta (en radianes) que `needs_split` sigue partiendo
+const MIN_EDGE: f32 = 0.004;
+/// Error de altura (fracción del radio) a partir del cual `needs_split` parte una arista
+const SPLIT_ERROR: f32 = 0.0015;
+
 impl Default for Rocky {
     fn default() -> Self {
         Self {
-            scale: 1.0,
-            rot_speed: 0.04,
-            orbit_radius: 0.0,
-            orbit_speed: 0.0,
-            orbit_world: false,
             sand:   Vec3::new(0.84, 0.58, 0.38),
             rust:   Vec3::new(0.65, 0.30, 0.20),
             basalt: Vec3::new(0.30, 0.15, 0.12),
             spec_intensity: 0.35,
             spec_power: 28.0,
+            displacement: 0.05,
         }
     }
 }
@@ -92,13 +78,59 @@ impl Rocky {
     #[inline]
     fn uv_from_normal(n: Vec3) -> (f32, f32) {
         let u = n.z.atan2(n.x) / (2.0*PI) + 0.5;
-        let v = n.y.asin() / PI + 0.5;
+        let v = n.y.clamp(-1.0, 1.0).asin() / PI + 0.5;
         (u, v)
     }
 
-    fn color_layers(&self, nrm_ws: Vec3, uv: Vec2, view_dir: Vec3, u: &Uniforms) -> Vec3 {
+    /// Relieve (0..1) en las UV de la superficie: el campo de cráteres de `ridge2`.
+    /// Cerca de u = 1 se mezcla con la muestra en u - 1 para que no haya salto en la costura.
+    /// La geometría usa `time = 0`: así el relieve queda fijo y el refinado de la malla
+    /// (hecho una sola vez) sigue sirviendo; el color sí lo anima.
+    fn relief(uv: Vec2, time: f32) -> f32 {
+        let at = |x: f32| ridge2(x * 5.5, uv.y * 5.5, time * 0.05);
+        let seam = smoothstep(0.9, 1.0, uv.x);
+        at(uv.x) * (1.0 - seam) + at(uv.x - 1.0) * seam
+    }
+
+    /// Radio relativo de la superficie (1 = esfera base) en la dirección `dir` (espacio objeto).
+    /// En los polos las UV se pellizcan, así que ahí el relieve se apaga.
+    pub fn surface_scale(&self, dir: Vec3) -> f32 {
+        let (su, sv) = Self::uv_from_normal(dir);
+        let polar = 1.0 - smoothstep(0.38, 0.48, (sv - 0.5).abs());
+        1.0 + self.displacement * (Self::relief(Vec2::new(su, sv), 0.0) - 0.5) * polar
+    }
+
+    /// Desplaza radialmente un punto de la esfera base y calcula la normal de la superficie
+    /// desplazada con diferencias finitas en dos direcciones tangentes
+    fn displace(&self, p: Vec3) -> (Vec3, Vec3) {
+        let r = p.length();
+        if r == 0.0 { return (p, p); }
+        let dir = p / r;
+        let surface = |d: Vec3| d * (r * self.surface_scale(d));
+        let helper = if dir.y.abs() < 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let t1 = dir.cross(helper).normalize();
+        let t2 = dir.cross(t1);
+        let p0 = surface(dir);
+        let p1 = surface((dir + t1 * NORMAL_EPS).normalize());
+        let p2 = surface((dir + t2 * NORMAL_EPS).normalize());
+        let n = (p1 - p0).cross(p2 - p0).normalize();
+        (p0, if n.dot(dir) < 0.0 { -n } else { n })
+    }
+
+    /// Criterio para `Mesh::refine_sphere`: parte la arista `a`-`b` si el relieve en su punto
+    /// medio se aleja de la interpolación lineal entre los extremos
+    pub fn needs_split(&self, a: Vec3, b: Vec3) -> bool {
+        if self.displacement <= 0.0 { return false; }
+        let (da, db) = (a.normalize(), b.normalize());
+        if da.dot(db) > MIN_EDGE.cos() { return false; }
+        let mid = self.surface_scale((da + db).normalize());
+        let linear = 0.5 * (self.surface_scale(da) + self.surface_scale(db));
+        (mid - linear).abs() > SPLIT_ERROR
+    }
+
+    fn color_layers(&self, nrm_ws: Vec3, uv: Vec2, view_dir: Vec3, vis: f32, u: &Uniforms) -> Vec3 {
         let n = nrm_ws.normalize();
-        let (mut uvs, vvs) = (uv.x, uv.y);
+        let (uvs, vvs) = (uv.x, uv.y);
         // 1) BASE
         let lat = (vvs - 0.5).abs(); // 0 en ecuador
         let base_lat = mix3(self.rust, self.sand, smoothstep(0.0, 0.45, 0.5 - lat));
@@ -111,7 +143,8 @@ impl Rocky {
         let with_albedo = mix3(base, self.basalt*0.9, albedo_mask*0.65);
 
         // 3) RELIEVE / CRÁTERES 
-    let relief = ridge2(uvs*5.5, vvs*5.5, u.time*0.05);
+        // El mismo relieve estático que desplaza la geometría: las crestas caen en los bordes
+        let relief = Self::relief(uv, 0.0);
     let micro  = fbm2(uvs*28.0, vvs*28.0, u.time*0.02, 3);
         let detail = (0.4*relief + 0.6*micro).clamp(0.0, 1.0);
         let rocky = with_albedo * (0.90 + 0.10*detail);
@@ -119,38 +152,40 @@ impl Rocky {
         let with_poles = mix3(rocky, Vec3::new(0.92, 0.92, 0.94), polar*0.75);
 
         // 4) LUZ
-        let ndl = lambert(n, u.light_dir);
+        let ndl = lambert(n, u.light_dir) * vis;
         let hemi = 0.18 + 0.82*ndl;
         let mut lit = with_poles * hemi;
         let l = (-u.light_dir).normalize();
         let refl = (n * (2.0 * n.dot(l)) - l).normalize();
-        let spec = (refl.dot(view_dir).max(0.0)).powf(self.spec_power) * self.spec_intensity;
+        let spec = (refl.dot(view_dir).max(0.0)).powf(self.spec_power) * self.spec_intensity * vis;
         lit += Vec3::new(1.0, 0.9, 0.8) * spec;
 
         let rim = (1.0 - n.dot(view_dir).max(0.0)).powf(3.0);
         lit += Vec3::new(1.0, 0.45, 0.25) * rim * 0.06;
 
-        lit.clamp01()
+        lit
     }
 }
 
 impl Shader for Rocky {
     fn vertex(&mut self, vin: VertexIn, u: &Uniforms) -> VertexOut {
-    let orbit_rot = rotation_y(u.time * u.planet.rotation_speed * 0.7);
-    let self_rot = rotation_y(u.time * self.rot_speed);
-    let model = orbit_rot * u.model * self_rot;
+        let (pos, nrm) = if self.displacement > 0.0 { self.displace(vin.pos) } else { (vin.pos, vin.nrm) };
 
-    let clip = u.proj * u.view * model * Vec4::from3(vin.pos, 1.0);
-    let pos_ws4 = model * Vec4::from3(vin.pos, 1.0);
-    let nrm_ws  = (model * Vec4::from3(vin.nrm, 0.0)).xyz().normalize();
-
-        let (su, sv) = Self::uv_from_normal(nrm_ws);
-        VertexOut { clip_pos: clip, pos_ws: pos_ws4.xyz(), nrm_ws, uv: Vec2::new(su, sv) }
+        let clip = u.proj * u.view * u.model * Vec4::from3(pos, 1.0);
+        let pos_ws = (u.model * Vec4::from3(pos, 1.0)).xyz();
+        let nrm_ws = (u.model * Vec4::from3(nrm, 0.0)).xyz().normalize();
+        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv, pos_os: pos }
     }
 
-    fn fragment(&mut self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> Color {
+    fn fragment(&self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> ColorF {
+        // UV de la superficie por fragmento, desde la dirección en espacio objeto: el relieve
+        // gira con el planeta y la costura de u no se interpola a lo ancho de un triángulo
+        let dir = vary.pos_os.normalize();
+        let (su, sv) = Self::uv_from_normal(dir);
+
         let view_dir = (u.camera_pos - vary.pos_ws).normalize();
-        let c = self.color_layers(vary.nrm_ws, vary.uv, view_dir, u);
+        let vis = shadow(u, vary.pos_ws, vary.nrm_ws);
+        let c = self.color_layers(vary.nrm_ws, Vec2::new(su, sv), view_dir, vis, u);
         to_color(c)
     }
 }
@@ -1,5 +1,5 @@
-use crate::math::{Vec2, Vec3, Vec4, rotation_y};
-use crate::renderer::{buffers::Color, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
+use crate::math::{Vec3, Vec4};
+use crate::renderer::{buffers::ColorF, uniforms::Uniforms, pipeline::{Shader, VertexIn, VertexOut}};
 use super::common::*;
 
 #[derive(Copy, Clone, Debug)]
@@ -25,10 +25,11 @@ impl Default for SciFi {
 }
 
 impl SciFi {
-    fn four_layer_gradient(&self, n_ws: Vec3, u: &Uniforms) -> Vec3 {
-        let mut t = 0.5 + 0.5 * n_ws.y; // 0..1
+    /// `n_os`: normal en espacio del objeto, las capas giran con el planeta
+    fn four_layer_gradient(&self, n_os: Vec3, u: &Uniforms) -> Vec3 {
+        let mut t = 0.5 + 0.5 * n_os.y; // 0..1
 
-        let noise = fbm_3d(n_ws * 6.0 + Vec3::new(u.time * 0.12, 0.0, u.time * 0.07), 4, 2.0, 0.5, self.noise_scale * u.planet.noise_scale);
+        let noise = fbm_3d(n_os * 6.0 + Vec3::new(u.time * 0.12, 0.0, u.time * 0.07), 4, 2.0, 0.5, self.noise_scale * u.planet.noise_scale);
         t = (t + noise * 0.08).clamp(0.0, 1.0);
 
         let centers = [0.125_f32, 0.375_f32, 0.625_f32, 0.875_f32];
@@ -50,31 +51,29 @@ impl Shader for SciFi {
     fn name(&self) -> &'static str { "SciFiPlanet" }
 
     fn vertex(&mut self, vin: VertexIn, u: &Uniforms) -> VertexOut {
-        let rot = rotation_y(u.time * u.planet.rotation_speed * 1.2);
-        let model = rot * u.model;
+        let clip = u.proj * u.view * u.model * Vec4::from3(vin.pos, 1.0);
+        let pos_ws = (u.model * Vec4::from3(vin.pos, 1.0)).xyz();
+        let nrm_ws = (u.model * Vec4::from3(vin.nrm, 0.0)).xyz().normalize();
 
-        let clip = u.proj * u.view * model * Vec4::from3(vin.pos, 1.0);
-        let pos_ws = (model * Vec4::from3(vin.pos, 1.0)).xyz();
-        let nrm_ws = (model * Vec4::from3(vin.nrm, 0.0)).xyz().normalize();
-
-        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv }
+        VertexOut { clip_pos: clip, pos_ws, nrm_ws, uv: vin.uv, pos_os: vin.pos }
     }
 
-    fn fragment(&mut self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> Color {
+    fn fragment(&self, vary: &crate::renderer::raster::Varyings, u: &Uniforms) -> ColorF {
         let view_dir = (u.camera_pos - vary.pos_ws).normalize();
-        let col = self.four_layer_gradient(vary.nrm_ws, u);
+        let n_os = vary.pos_os.normalize();
+        let col = self.four_layer_gradient(n_os, u);
 
 
-        let diff = lambert(vary.nrm_ws, u.light_dir);
+        let diff = lambert(vary.nrm_ws, u.light_dir) * shadow(u, vary.pos_ws, vary.nrm_ws);
         let lit = col * (0.45 + 0.55 * diff);
         let rim_k = rim(vary.nrm_ws, view_dir, 4.0);
         let rim_color = self.glow_color * rim_k * 0.9;
-        let band_t = 0.5 + 0.5 * vary.nrm_ws.y;
-        let band_noise = fbm_3d(vary.pos_ws * 3.0 + Vec3::new(u.time*0.6, 0.0, 0.0), 3, 2.0, 0.5, self.noise_scale);
+        let band_t = 0.5 + 0.5 * n_os.y;
+        let band_noise = fbm_3d(vary.pos_os * 3.0 + Vec3::new(u.time*0.6, 0.0, 0.0), 3, 2.0, 0.5, self.noise_scale);
         let band = ((band_t * 10.0 + band_noise*2.0).fract() - 0.5).abs();
         let band_emis = (1.0 - (band * 20.0).clamp(0.0,1.0)).powf(2.0) * 0.6;
         let emis = self.glow_color * band_emis;
 
-        to_color((lit + rim_color + emis).clamp01())
+        to_color(lit + rim_color + emis)
     }
 }
@@ -0,0 +1,198 @@
+/// Valor JSON. Los objetos conservan el orden de las claves.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parsea un documento completo. Los errores indican `línea:columna`.
+    pub fn parse(src: &str) -> Result<Json, String> {
+        let mut p = Parser { src: src.as_bytes(), pos: 0 };
+        let value = p.value(0)?;
+        p.skip_ws();
+        if p.pos < p.src.len() { return Err(p.err("texto de más después del valor")); }
+        Ok(value)
+    }
+
+    /// Campo de un objeto (`None` si no es objeto o no está)
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self { Json::Number(n) => Some(n), _ => None }
+    }
+
+    /// Entero no negativo exacto (índices, tamaños)
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_f64().filter(|n| *n >= 0.0 && n.fract() == 0.0 && *n <= u32::MAX as f64).map(|n| n as usize)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self { Json::Bool(b) => Some(b), _ => None }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self { Json::String(s) => Some(s), _ => None }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self { Json::Array(a) => Some(a), _ => None }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, Json)]> {
+        match self { Json::Object(o) => Some(o), _ => None }
+    }
+}
+
+/// Anidamiento máximo, para no desbordar la pila con entradas hostiles
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn err(&self, msg: &str) -> String {
+        let before = &self.src[..self.pos.min(self.src.len())];
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let col = before.iter().rev().take_while(|&&b| b != b'\n').count() + 1;
+        format!("{line}:{col}: {msg}")
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.src.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) { self.pos += 1; }
+    }
+
+    fn eat(&mut self, lit: &str) -> bool {
+        if self.src[self.pos..].starts_with(lit.as_bytes()) { self.pos += lit.len(); true } else { false }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH { return Err(self.err("anidamiento demasiado profundo")); }
+        self.skip_ws();
+        match self.src.get(self.pos) {
+            None => Err(self.err("se esperaba un valor y terminó el texto")),
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_ws();
+                if self.eat("}") { return Ok(Json::Object(fields)); }
+                loop {
+                    self.skip_ws();
+                    if self.src.get(self.pos) != Some(&b'"') { return Err(self.err("se esperaba una clave entre comillas")); }
+                    let key = self.string()?;
+                    self.skip_ws();
+                    if !self.eat(":") { return Err(self.err("se esperaba ':'")); }
+                    fields.push((key, self.value(depth + 1)?));
+                    self.skip_ws();
+                    if self.eat("}") { return Ok(Json::Object(fields)); }
+                    if !self.eat(",") { return Err(self.err("se esperaba ',' o '}'")); }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_ws();
+                if self.eat("]") { return Ok(Json::Array(items)); }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_ws();
+                    if self.eat("]") { return Ok(Json::Array(items)); }
+                    if !self.eat(",") { return Err(self.err("se esperaba ',' o ']'")); }
+                }
+            }
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ if self.eat("true") => Ok(Json::Bool(true)),
+            _ if self.eat("false") => Ok(Json::Bool(false)),
+            _ if self.eat("null") => Ok(Json::Null),
+            Some(_) => Err(self.err("valor inválido")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while matches!(self.src.get(self.pos), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) { self.pos += 1; }
+        let text = std::str::from_utf8(&self.src[start..self.pos]).unwrap_or("");
+        match text.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(Json::Number(n)),
+            _ => { self.pos = start; Err(self.err(&format!("número inválido '{text}'"))) }
+        }
+    }
+
+    /// Cadena con sus escapes; `pos` está en la comilla de apertura
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut out: Vec<u8> = Vec::new();
+        loop {
+            let Some(&b) = self.src.get(self.pos) else { return Err(self.err("cadena sin cerrar")); };
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&e) = self.src.get(self.pos) else { return Err(self.err("cadena sin cerrar")); };
+                    self.pos += 1;
+                    let c = match e {
+                        b'"' => '"', b'\\' => '\\', b'/' => '/',
+                        b'b' => '\u{8}', b'f' => '\u{c}', b'n' => '\n', b'r' => '\r', b't' => '\t',
+                        b'u' => {
+                            let hi = self.hex4()?;
+                            // Pares sustitutos de UTF-16
+                            let code = if (0xD800..0xDC00).contains(&hi) && self.eat("\\u") {
+                                let lo = self.hex4()?;
+                                0x10000 + ((hi - 0xD800) << 10) + (lo.wrapping_sub(0xDC00) & 0x3FF)
+                            } else {
+                                hi
+                            };
+                            char::from_u32(code).unwrap_or('\u{FFFD}')
+                        }
+                        _ => { self.pos -= 1; return Err(self.err("escape inválido")); }
+                    };
+                    out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                0..=0x1F => { self.pos -= 1; return Err(self.err("carácter de control en una cadena")); }
+                _ => out.push(b),
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.err("cadena con UTF-8 inválido"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.src.get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.err("escape \\u inválido"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_documents_and_reports_position() {
+        let doc = Json::parse(r#"{ "a": [1, -2.5e1, true, null], "b": {"c": "x\"é\n"}, "a": 3 }"#).unwrap();
+        let a = doc.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a, &[Json::Number(1.0), Json::Number(-25.0), Json::Bool(true), Json::Null]);
+        assert_eq!(doc.get("b").and_then(|b| b.get("c")).and_then(Json::as_str), Some("x\"é\n"));
+        assert_eq!(Json::Number(3.0).as_usize(), Some(3));
+        assert_eq!(Json::Number(-1.0).as_usize(), None);
+
+        assert_eq!(Json::parse("{\n  \"a\": tru }").unwrap_err(), "2:8: valor inválido");
+        assert_eq!(Json::parse("[1, 2").unwrap_err(), "1:6: se esperaba ',' o ']'");
+        assert!(Json::parse("[1] 2").unwrap_err().contains("de más"));
+        assert!(Json::parse(&"[".repeat(1000)).unwrap_err().contains("profundo"));
+    }
+}
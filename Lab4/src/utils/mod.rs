@@ -1 +1,4 @@
 pub mod timer;
+pub mod json;
+#[cfg(test)]
+pub(crate) mod testing;
@@ -0,0 +1,24 @@
+//! Preparación común de los tests unitarios
+
+use std::path::PathBuf;
+
+use crate::math::{Mat4, Vec3, viewport};
+use crate::renderer::Uniforms;
+use crate::scene::{Camera, Mesh, load_obj};
+
+/// Directorio temporal del test, `lab4_<name>_<pid>`. Lo borra el test al terminar.
+pub fn temp_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("lab4_{name}_{}", std::process::id()))
+}
+
+/// La esfera de `assets/sphere.obj`
+pub fn sphere() -> Mesh {
+    load_obj("assets/sphere.obj").unwrap()
+}
+
+/// Uniforms de una cámara en `eye` que mira al origen y viewport de `width`x`height`
+pub fn looking_at_origin(eye: Vec3, width: usize, height: usize, time: f32) -> (Uniforms, Mat4) {
+    let cam = Camera { eye, aspect: width as f32 / height as f32, ..Default::default() };
+    let u = Uniforms { view: cam.view(), proj: cam.proj(), camera_pos: cam.eye, time, ..Default::default() };
+    (u, viewport(0.0, 0.0, width as f32, height as f32, 1.0))
+}
@@ -6,6 +6,10 @@ pub struct FpsCounter {
     frames: u32,
 }
 
+impl Default for FpsCounter {
+    fn default() -> Self { Self::new() }
+}
+
 impl FpsCounter {
     pub fn new() -> Self {
         Self { last: Instant::now(), acc: Duration::from_secs(0), frames: 0 }
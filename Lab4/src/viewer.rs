@@ -0,0 +1,283 @@
+use std::time::Instant;
+
+use lab4::headless::RenderOptions;
+use lab4::scene::{Camera, Input, Action, Scene, SceneWatcher};
+use lab4::shaders::{ShaderKind, ShaderParams};
+
+use pixels::{Pixels, SurfaceTexture};
+use winit::{
+    dpi::LogicalSize,
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
+
+/// Ventana interactiva
+pub fn run(opts: RenderOptions) -> Result<(), String> {
+    let width = opts.width as u32;
+    let height = opts.height as u32;
+
+    let event_loop = EventLoop::new();
+    let window = WindowBuilder::new()
+        .with_title("Lab 04 - Static Shaders (Rust)")
+        .with_inner_size(LogicalSize::new(width as f64, height as f64))
+        .build(&event_loop)
+        .map_err(|e| format!("Window: {e:?}"))?;
+
+    let surface_texture = SurfaceTexture::new(width, height, &window);
+    let mut pixels = Pixels::new(width, height, surface_texture)
+        .map_err(|e| format!("Pixels: {e}"))?;
+
+    // Framebuffer
+    let fb_settings = opts.framebuffer.clone();
+    let mut fb = fb_settings.build(opts.width, opts.height)?;
+    let mut shadow_map = None;
+    if fb.post.is_active() { println!("Post-proceso: {}", fb.post.enabled().join(" -> ")); }
+
+    // Escena y mallas
+    let mut scene = opts.load_scene()?;
+    let mut meshes = opts.load_meshes(&scene)?;
+    let mesh = &meshes.default;
+    println!("OK {}: {} vértices, {} triángulos", opts.meshes.default, mesh.vertices.len(), mesh.indices.len());
+    for ((kind, src), (_, m)) in opts.meshes.overrides.iter().zip(&meshes.overrides) {
+        println!("OK {kind:?} {src}: {} vértices, {} triángulos", m.vertices.len(), m.indices.len());
+    }
+    if opts.scene.terrain > 0.0 {
+        let m = meshes.get(ShaderKind::Rocky);
+        println!("Relieve Rocky: {} vértices, {} triángulos", m.vertices.len(), m.indices.len());
+    }
+    
+    // DEBUG: Verificar bounds del mesh
+    if !mesh.vertices.is_empty() {
+        let first = mesh.vertices[0].pos;
+        println!("Primer vértice: ({}, {}, {})", first.x, first.y, first.z);
+    }
+
+    // Cámara 
+    let mut cam = scene.camera;
+    
+    println!("Cámara inicial: eye={:?}, center={:?}", cam.eye, cam.center);
+
+    // Input 
+    let mut input = Input::new();
+
+    // Estado app: con --scene las teclas de planetas, anillos y luna no cambian nada
+    let mut showcase = opts.scene;
+    if let Some(path) = &opts.scene_file { println!("Escena: {} (se recarga al guardarla)", path.display()); }
+    let mut watcher = opts.scene_file.as_deref().map(SceneWatcher::new);
+    let mut sim_time = opts.start_time;
+    let mut running = true;
+
+    let mut last = Instant::now();
+    let mut frame_count = 0;
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Poll;
+
+        match event {
+            Event::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => {
+                    running = false;
+                    *control_flow = ControlFlow::Exit;
+                }
+                WindowEvent::KeyboardInput { input: KeyboardInput { state, virtual_keycode: Some(vk), .. }, .. } => {
+                    let is_down = state == ElementState::Pressed;
+                    let action_opt = match vk {
+                        // Movimiento
+                        VirtualKeyCode::W => Some(Action::MoveForward),
+                        VirtualKeyCode::S => Some(Action::MoveBackward),
+                        VirtualKeyCode::A => Some(Action::MoveLeft),
+                        VirtualKeyCode::D => Some(Action::MoveRight),
+                        VirtualKeyCode::Space => Some(Action::MoveUp),
+                        VirtualKeyCode::LShift => Some(Action::MoveDown),
+
+                        // Rotación cámara
+                        VirtualKeyCode::Left  => Some(Action::YawLeft),
+                        VirtualKeyCode::Right => Some(Action::YawRight),
+                        VirtualKeyCode::Up    => Some(Action::PitchUp),
+                        VirtualKeyCode::Down  => Some(Action::PitchDown),
+
+                        // Shaders
+                        VirtualKeyCode::Key1 => Some(Action::Shader1),
+                        VirtualKeyCode::Key2 => Some(Action::Shader2),
+                        VirtualKeyCode::Key3 => Some(Action::Shader3),
+                        VirtualKeyCode::Numpad1 => Some(Action::Shader1),
+                        VirtualKeyCode::Numpad2 => Some(Action::Shader2),
+                        VirtualKeyCode::Numpad3 => Some(Action::Shader3),
+                        VirtualKeyCode::Key0 => { if is_down { showcase.show_all = !showcase.show_all; } None },
+                        VirtualKeyCode::Numpad0 => { if is_down { showcase.show_all = !showcase.show_all; } None },
+                        VirtualKeyCode::Key4 => Some(Action::Shader4),
+                        VirtualKeyCode::Key5 => Some(Action::Shader5),
+                        VirtualKeyCode::Numpad4 => Some(Action::Shader4),
+                        VirtualKeyCode::Numpad5 => Some(Action::Shader5),
+
+                        // Toggles / util
+                        VirtualKeyCode::R => Some(Action::ToggleRings),
+                        VirtualKeyCode::M => Some(Action::ToggleMoon),
+                        VirtualKeyCode::P => Some(Action::Screenshot),
+                        VirtualKeyCode::Escape => Some(Action::Quit),
+
+                        // Post-proceso
+                        VirtualKeyCode::F1 | VirtualKeyCode::F2 | VirtualKeyCode::F3 | VirtualKeyCode::F4 | VirtualKeyCode::F5 => {
+                            if is_down {
+                                let name = match vk {
+                                    VirtualKeyCode::F1 => "bloom",
+                                    VirtualKeyCode::F2 => "fxaa",
+                                    VirtualKeyCode::F3 => "vignette",
+                                    VirtualKeyCode::F4 => "grain",
+                                    _ => "lut",
+                                };
+                                match fb.post.toggle_or_add(name) {
+                                    Ok(on) => println!("Post {name}: {}", if on { "on" } else { "off" }),
+                                    Err(e) => eprintln!("{e}"),
+                                }
+                            }
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(a) = action_opt {
+                        println!("Key event: {:?} down={} action={:?}", vk, is_down, a);
+                        if is_down { input.action_down(a); } else { input.action_up(a); }
+                    } else {
+                        println!("Key event: {:?} down={} (no action)", vk, is_down);
+                    }
+                }
+                WindowEvent::Resized(size) => {
+                    let _ = pixels.resize_surface(size.width, size.height);
+                    let _ = pixels.resize_buffer(size.width, size.height);
+                    cam.set_aspect(size.width as f32 / size.height as f32);
+                }
+                _ => {}
+            },
+
+            // Lógica y pedir redraw
+            Event::MainEventsCleared => {
+                if !running { *control_flow = ControlFlow::Exit; return; }
+
+                let now = Instant::now();
+                let dt = (now - last).as_secs_f32();
+                last = now;
+
+                if input.is_pressed(Action::Quit) { *control_flow = ControlFlow::Exit; return; }
+                if input.is_pressed(Action::Shader1) { println!("Action pressed: Shader1"); showcase.shader = ShaderKind::Rocky; showcase.show_all = false; }
+                if input.is_pressed(Action::Shader2) { println!("Action pressed: Shader2"); showcase.shader = ShaderKind::Gas; showcase.show_all = false; }
+                if input.is_pressed(Action::Shader3) { println!("Action pressed: Shader3"); showcase.shader = ShaderKind::SciFi; showcase.show_all = false; }
+                if input.is_pressed(Action::Shader4) { println!("Action pressed: Shader4"); showcase.shader = ShaderKind::Lava; showcase.show_all = false; }
+                if input.is_pressed(Action::Shader5) { println!("Action pressed: Shader5"); showcase.shader = ShaderKind::Ice;  showcase.show_all = false; }
+                if input.is_pressed(Action::ToggleRings) { showcase.show_rings = !showcase.show_rings; }
+                if input.is_pressed(Action::ToggleMoon)  { showcase.show_moon  = !showcase.show_moon; }
+
+                update_camera(&mut cam, &input, dt);
+
+                // Recarga en caliente: si el archivo tiene errores sigue la última escena buena
+                if let Some(w) = &mut watcher && let Some(reloaded) = w.poll() {
+                    let applied = reloaded.and_then(|new| {
+                        let new = opts.fit_scene(new);
+                        if terrain(&new) != terrain(&scene) { meshes = opts.load_meshes(&new)?; }
+                        Ok(new)
+                    });
+                    match applied {
+                        Ok(new) => {
+                            // La cámara del usuario solo se pisa si el archivo cambió la suya
+                            if !same_camera(&new.camera, &scene.camera) {
+                                cam = Camera { aspect: cam.aspect, ..new.camera };
+                            }
+                            scene = new;
+                            println!("Escena recargada: {}", w.path.display());
+                        }
+                        Err(e) => eprintln!("{e} (sigue la escena anterior)"),
+                    }
+                }
+
+                sim_time += dt;
+
+                window.request_redraw();
+            }
+
+            // Render 
+            Event::RedrawRequested(_) => {
+                frame_count += 1;
+
+                let size = window.inner_size();
+                let fw = size.width as usize;
+                let fh = size.height as usize;
+
+                if fb.width != fw || fb.height != fh {
+                    // Conserva los toggles de post-proceso hechos en runtime
+                    let post = std::mem::take(&mut fb.post);
+                    fb = match fb_settings.build(fw, fh) {
+                        Ok(fb) => fb,
+                        Err(e) => { eprintln!("{e}"); *control_flow = ControlFlow::Exit; return; }
+                    };
+                    fb.post = post;
+                    cam.set_aspect(fw as f32 / fh as f32);
+                }
+
+                // Render 
+                if opts.scene_file.is_none() { scene = showcase.scene(); }
+                scene.advance(sim_time);
+                let uniforms = scene.uniforms(&cam, sim_time);
+                let stats = scene.render(&mut fb, &meshes, &uniforms, &mut shadow_map);
+                fb.post.frame = opts.seed.wrapping_add(frame_count as u64);
+
+                // DEBUG
+                if frame_count % 60 == 0 {
+                    println!("Frame {}: cam.eye={:?}, triángulos={}, fragments={} (early-z evitó {})", 
+                             frame_count, cam.eye, stats.triangles, stats.fragments_shaded, stats.fragments_early_rejected);
+                    if let Some(sim) = &scene.gravity {
+                        println!("Gravedad: {} pasos, deriva de energía {:.2e} (máx {:.2e})",
+                                 sim.steps(), sim.energy_drift(), sim.max_energy_drift());
+                    }
+                }
+
+                if input.is_pressed(Action::Screenshot) {
+                    std::fs::create_dir_all(&opts.out_dir).ok();
+                    let path = opts.out_dir.join(format!("frame_{:.0}.png", uniforms.time*1000.0));
+                    match path.to_str().map(|p| fb.save_png(p)) {
+                        Some(Ok(())) => println!("Guardado: {}", path.display()),
+                        Some(Err(e)) => eprintln!("{e}"),
+                        None => eprintln!("ruta no UTF-8: {}", path.display()),
+                    }
+                }
+
+                let resolved = fb.resolve();
+                let frame = pixels.frame_mut();
+                for (px, c) in frame.chunks_exact_mut(4).zip(resolved) {
+                    px.copy_from_slice(&c.to_rgba());
+                }
+
+                if let Err(e) = pixels.render() {
+                    eprintln!("pixels.render: {e}");
+                }
+                input.begin_frame();
+            }
+
+            _ => {}
+        }
+    });
+}
+
+// Helpers 
+fn update_camera(cam: &mut Camera, input: &Input, dt: f32) {
+    let fwd   = (input.is_held(Action::MoveForward) as i32 - input.is_held(Action::MoveBackward) as i32) as f32;
+    let right = (input.is_held(Action::MoveRight)   as i32 - input.is_held(Action::MoveLeft)    as i32) as f32;
+    let up    = (input.is_held(Action::MoveUp)      as i32 - input.is_held(Action::MoveDown)    as i32) as f32;
+    cam.move_free(fwd, right, up, dt);
+
+    let yaw   = (input.is_held(Action::YawRight) as i32 - input.is_held(Action::YawLeft)  as i32) as f32;
+    let pitch = (input.is_held(Action::PitchUp)  as i32 - input.is_held(Action::PitchDown)as i32) as f32;
+    cam.rotate_free(yaw, pitch, dt);
+}
+
+/// Altura del relieve del planeta rocoso, que decide su malla
+fn terrain(scene: &Scene) -> f32 {
+    match scene.root.find_shader(ShaderKind::Rocky, 0.0) {
+        Some((ShaderParams::Rocky(r), _)) => r.displacement,
+        _ => 0.0,
+    }
+}
+
+fn same_camera(a: &Camera, b: &Camera) -> bool {
+    a.eye == b.eye && a.center == b.center && a.fov_y == b.fov_y
+}
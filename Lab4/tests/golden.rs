@@ -0,0 +1,136 @@
+//! Regresión visual: cada `ShaderKind` se renderiza por el camino headless con tiempo,
+//! cámara y resolución fijos y se compara contra `tests/golden/<planeta>.png`.
+//!
+//! - Regenerar las referencias: `LAB4_BLESS=1 cargo test --test golden`
+//! - Si una imagen no coincide, en `target/tmp/golden/` quedan `<planeta>.png` (render
+//!   actual) y `<planeta>_diff.png` (referencia atenuada, en rojo los píxeles distintos).
+
+use std::path::{Path, PathBuf};
+
+use image::{Rgba, RgbaImage};
+use lab4::headless::{RenderOptions, render_headless};
+use lab4::scene::{MeshSource, PlanetMeshes, Showcase};
+use lab4::shaders::ShaderKind;
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 180;
+const TIME: f32 = 1.5;
+
+/// Diferencia perceptual por píxel (0..1) a partir de la cual el píxel cuenta como distinto
+const PIXEL_THRESHOLD: f32 = 0.03;
+/// Fracción de píxeles distintos tolerada (ruido de punto flotante entre plataformas)
+const MAX_DIFF_FRACTION: f32 = 0.002;
+
+const PLANETS: [(ShaderKind, &str); 8] = [
+    (ShaderKind::Rocky, "rocky"),
+    (ShaderKind::Gas, "gas"),
+    (ShaderKind::SciFi, "scifi"),
+    (ShaderKind::Rings, "rings"),
+    (ShaderKind::Moon, "moon"),
+    (ShaderKind::Flat, "flat"),
+    (ShaderKind::Lava, "lava"),
+    (ShaderKind::Ice, "ice"),
+];
+
+fn golden_dir() -> PathBuf { Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden") }
+fn out_dir() -> PathBuf { Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden") }
+
+/// Un planeta solo, sin luna ni anillos extra, para aislar cada shader
+fn render(kind: ShaderKind, name: &str) -> RgbaImage {
+    let opts = RenderOptions {
+        width: WIDTH, height: HEIGHT, frames: 1, start_time: TIME,
+        out_dir: out_dir().join(name),
+        meshes: PlanetMeshes::new(MeshSource::File(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sphere.obj").into())),
+        scene: Showcase { shader: kind, show_all: false, show_rings: false, show_moon: false, ..Default::default() },
+        ..Default::default()
+    };
+    let paths = render_headless(&opts).unwrap();
+    let img = image::open(&paths[0]).unwrap().to_rgba8();
+    std::fs::remove_dir_all(&opts.out_dir).ok();
+    img
+}
+
+/// Distancia de color en YIQ (la de pixelmatch), normalizada a 0..1
+fn color_delta(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let yiq = |p: &Rgba<u8>| {
+        let [r, g, b] = [p[0] as f32, p[1] as f32, p[2] as f32];
+        (
+            0.298_895_3 * r + 0.586_622_5 * g + 0.114_482_2 * b,
+            0.595_977_99 * r - 0.274_176_4 * g - 0.321_801_6 * b,
+            0.211_470_19 * r - 0.522_617_4 * g + 0.311_147_2 * b,
+        )
+    };
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    let (y, i, q) = (ya - yb, ia - ib, qa - qb);
+    let d = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+    (d / 35215.0).sqrt()
+}
+
+/// Cuenta los píxeles distintos y arma la imagen de diferencias
+fn compare(actual: &RgbaImage, expected: &RgbaImage) -> (usize, RgbaImage) {
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut bad = 0;
+    for ((a, e), d) in actual.pixels().zip(expected.pixels()).zip(diff.pixels_mut()) {
+        *d = if color_delta(a, e) > PIXEL_THRESHOLD {
+            bad += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let l = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3;
+            let g = (255 - (255 - l) / 4) as u8;
+            Rgba([g, g, g, 255])
+        };
+    }
+    (bad, diff)
+}
+
+#[test]
+fn shaders_match_golden_images() {
+    let bless = std::env::var_os("LAB4_BLESS").is_some();
+    let mut failures = Vec::new();
+
+    for (kind, name) in PLANETS {
+        let actual = render(kind, name);
+        let golden = golden_dir().join(format!("{name}.png"));
+
+        if bless {
+            std::fs::create_dir_all(golden_dir()).unwrap();
+            actual.save(&golden).unwrap();
+            continue;
+        }
+        let Ok(expected) = image::open(&golden).map(|i| i.to_rgba8()) else {
+            failures.push(format!("{name}: falta {} (genérala con LAB4_BLESS=1)", golden.display()));
+            continue;
+        };
+        if expected.dimensions() != actual.dimensions() {
+            failures.push(format!("{name}: tamaño {:?}, referencia {:?}", actual.dimensions(), expected.dimensions()));
+            continue;
+        }
+
+        let (bad, diff) = compare(&actual, &expected);
+        let fraction = bad as f32 / (WIDTH * HEIGHT) as f32;
+        if fraction > MAX_DIFF_FRACTION {
+            std::fs::create_dir_all(out_dir()).unwrap();
+            let actual_path = out_dir().join(format!("{name}.png"));
+            let diff_path = out_dir().join(format!("{name}_diff.png"));
+            actual.save(&actual_path).unwrap();
+            diff.save(&diff_path).unwrap();
+            failures.push(format!("{name}: {bad} píxeles distintos ({:.2}%), ver {}", fraction * 100.0, diff_path.display()));
+        }
+    }
+    assert!(failures.is_empty(), "imágenes distintas a la referencia:\n  {}", failures.join("\n  "));
+}
+
+#[test]
+fn diff_flags_changed_pixels_only() {
+    let a = RgbaImage::from_pixel(4, 4, Rgba([40, 80, 120, 255]));
+    let mut b = a.clone();
+    b.put_pixel(1, 2, Rgba([240, 80, 120, 255]));
+    // Un cambio de 1 nivel no es visible
+    b.put_pixel(3, 3, Rgba([41, 80, 120, 255]));
+
+    let (bad, diff) = compare(&b, &a);
+    assert_eq!(bad, 1);
+    assert_eq!(*diff.get_pixel(1, 2), Rgba([255, 0, 0, 255]));
+    assert_ne!(*diff.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+}